@@ -0,0 +1,192 @@
+//! Generation as a whole and stage by stage, so a regression shows up in the stage that caused it.
+//!
+//! Each stage runs on inputs made by the stages before it from fixed seeds, cycling through them so results are
+//! comparable between runs without depending on one grid.
+//!
+//! The `uniqueness` group counts the solutions of finished puzzles, which is the check generation repeats until a
+//! puzzle has only one. On a finished puzzle the search has to rule out every other solution, so it's the slowest
+//! case of that check.
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use generator::{
+    fill::fill_puzzle,
+    grid_gen::{
+        GenConfig, generate_grid, generate_puzzle,
+        stages::{self, PartialGrid},
+    },
+    puzzle::Puzzle,
+    uniqueness::count_solutions,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const SIZES: [u8; 5] = [8, 12, 16, 20, 30];
+
+const SEEDS: u64 = 16;
+
+fn config(size: u8) -> GenConfig {
+    GenConfig::new(size, size)
+}
+
+/// Cycles through `inputs`, handing out a fresh copy each time
+fn cycle<T: Clone>(inputs: &[T]) -> impl FnMut() -> T {
+    let mut i = 0;
+
+    move || {
+        i = (i + 1) % inputs.len();
+        inputs[i].clone()
+    }
+}
+
+/// Grids after anchor placement, with the rng to carry on from
+fn anchored(size: u8) -> Vec<(PartialGrid, ChaCha8Rng)> {
+    (0..SEEDS)
+        .map(|seed| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            (stages::place_anchors(&config(size), &mut rng), rng)
+        })
+        .collect()
+}
+
+/// Connected grids after growth, ready for fix-up
+fn grown(size: u8) -> Vec<PartialGrid> {
+    anchored(size)
+        .into_iter()
+        .map(|(mut partial, mut rng)| {
+            stages::grow(&mut partial, &mut rng);
+            partial
+        })
+        .filter(PartialGrid::is_connected)
+        .collect()
+}
+
+fn shapes(size: u8) -> Vec<Puzzle> {
+    (0..SEEDS)
+        .map(|seed| Puzzle::from_shape(&generate_grid(&config(size).with_seed(seed)).unwrap()))
+        .collect()
+}
+
+fn puzzles(size: u8) -> Vec<Puzzle> {
+    (0..SEEDS)
+        .map(|seed| generate_puzzle(&config(size).with_seed(seed)).unwrap())
+        .collect()
+}
+
+fn bench_generate_grid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_grid");
+
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let mut seed = 0;
+            b.iter(|| {
+                seed = (seed + 1) % SEEDS;
+                generate_grid(&config(size).with_seed(seed)).unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_anchors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("anchors");
+
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let rngs: Vec<_> = (0..SEEDS).map(ChaCha8Rng::seed_from_u64).collect();
+            b.iter_batched(
+                cycle(&rngs),
+                |mut rng| stages::place_anchors(&config(size), &mut rng),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_growth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("growth");
+
+    for size in SIZES {
+        let inputs = anchored(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &inputs, |b, inputs| {
+            b.iter_batched(
+                cycle(inputs),
+                |(mut partial, mut rng)| {
+                    stages::grow(&mut partial, &mut rng);
+                    partial
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_fix_up(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fix_up");
+
+    for size in SIZES {
+        let inputs = grown(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &inputs, |b, inputs| {
+            b.iter_batched(
+                cycle(inputs),
+                |mut partial| {
+                    stages::fix_up(&mut partial);
+                    partial
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fill");
+
+    for size in SIZES {
+        let inputs = shapes(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &inputs, |b, inputs| {
+            let mut rng = ChaCha8Rng::seed_from_u64(0);
+            b.iter_batched(
+                cycle(inputs),
+                |shape| fill_puzzle(&shape, &mut rng),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_uniqueness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uniqueness");
+
+    for size in SIZES {
+        let inputs = puzzles(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &inputs, |b, inputs| {
+            b.iter_batched(
+                cycle(inputs),
+                |puzzle| count_solutions(&puzzle, 2),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_generate_grid,
+    bench_anchors,
+    bench_growth,
+    bench_fix_up,
+    bench_fill,
+    bench_uniqueness
+);
+criterion_main!(benches);
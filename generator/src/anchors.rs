@@ -1,45 +1,157 @@
-use crate::grid_gen::{Anchor, Component};
-
-pub const ANCHORS_8X8_1: [Anchor; 4] = [
-    Anchor::new(0, 0, Component::new(2, 2)),
-    Anchor::new(6, 0, Component::new(2, 2)),
-    Anchor::new(0, 6, Component::new(2, 2)),
-    Anchor::new(6, 6, Component::new(2, 2)),
-];
-//pub const ANCHORS_8X8: [Anchor; 4] = [
-//    Anchor::new(0, 0, Component::new(2, 3)),
-//    Anchor::new(5, 0, Component::new(3, 2)),
-//    Anchor::new(0, 6, Component::new(3, 2)),
-//    Anchor::new(6, 5, Component::new(2, 3)),
-//];
-pub const ANCHORS_8X8_2: [Anchor; 9] = [
-    Anchor::new(0, 0, Component::new(2, 2)),
-    Anchor::new(3, 0, Component::new(2, 2)),
-    Anchor::new(6, 0, Component::new(2, 2)),
-    Anchor::new(0, 3, Component::new(2, 2)),
-    Anchor::new(3, 3, Component::new(2, 2)),
-    Anchor::new(6, 3, Component::new(2, 2)),
-    Anchor::new(0, 6, Component::new(2, 2)),
-    Anchor::new(3, 6, Component::new(2, 2)),
-    Anchor::new(6, 6, Component::new(2, 2)),
-];
-
-pub const ANCHORS_20X20: [Anchor; 9] = [
-    Anchor::new(0, 0, Component::new(5, 5)),
-    Anchor::new(7, 0, Component::new(5, 5)),
-    Anchor::new(15, 0, Component::new(5, 5)),
-    Anchor::new(0, 7, Component::new(5, 5)),
-    Anchor::new(7, 7, Component::new(5, 5)),
-    Anchor::new(15, 7, Component::new(5, 5)),
-    Anchor::new(0, 15, Component::new(5, 5)),
-    Anchor::new(7, 15, Component::new(5, 5)),
-    Anchor::new(15, 15, Component::new(5, 5)),
-];
+use std::fmt::Debug;
+
+use rand::{Rng, RngCore};
+
+pub use crate::grid_gen::{Anchor, Component};
+
+/// Decides where the initial components are placed before the growth loop fills in the rest of the grid
+pub trait AnchorStrategy: Debug + Send + Sync {
+    fn place_anchors(&self, width: u8, height: u8, rng: &mut dyn RngCore) -> Vec<Anchor>;
+}
+
+/// Component size and per-axis count used by the default lattice for a grid of this size
+fn lattice_params(width: u8, height: u8) -> ((u8, u8), (u8, u8)) {
+    let count = (
+        if width >= 8 { 3 } else { 2 },
+        if height >= 8 { 3 } else { 2 },
+    );
+    let comp = (2.max(width / 4), 2.max(height / 4));
+
+    (comp, count)
+}
+
+/// Evenly spaced grid of same-size components, touching every edge of the grid
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lattice;
+
+impl AnchorStrategy for Lattice {
+    fn place_anchors(&self, width: u8, height: u8, _rng: &mut dyn RngCore) -> Vec<Anchor> {
+        let (comp, count) = lattice_params(width, height);
+
+        generate_anchors((width, height), comp, count)
+    }
+}
+
+/// Same as `Lattice`, but each anchor is moved by up to `jitter` cells on each axis. The default with a jitter of 1,
+/// on some sizes below 20 the plain lattice leaves gaps the growth loop can never close
+#[derive(Clone, Copy, Debug)]
+pub struct JitteredLattice {
+    pub jitter: u8,
+}
+
+impl AnchorStrategy for JitteredLattice {
+    fn place_anchors(&self, width: u8, height: u8, rng: &mut dyn RngCore) -> Vec<Anchor> {
+        let jitter = self.jitter as i16;
+
+        Lattice
+            .place_anchors(width, height, rng)
+            .into_iter()
+            .map(|a| {
+                let max_x = (width - a.component.width) as i16;
+                let max_y = (height - a.component.height) as i16;
+
+                let x = (a.x as i16 + rng.random_range(-jitter..=jitter)).clamp(0, max_x);
+                let y = (a.y as i16 + rng.random_range(-jitter..=jitter)).clamp(0, max_y);
+
+                Anchor::new(x as u8, y as u8, a.component)
+            })
+            .collect()
+    }
+}
+
+/// Up to `count` randomly sized components at random positions, with at least one empty cell between any two
+#[derive(Clone, Copy, Debug)]
+pub struct RandomScatter {
+    pub count: usize,
+}
+
+impl AnchorStrategy for RandomScatter {
+    fn place_anchors(&self, width: u8, height: u8, rng: &mut dyn RngCore) -> Vec<Anchor> {
+        let max_w = 2.max(width / 4);
+        let max_h = 2.max(height / 4);
+
+        let mut res: Vec<Anchor> = Vec::new();
+
+        // Give up on a crowded grid instead of looping forever
+        for _ in 0..self.count * 20 {
+            if res.len() >= self.count {
+                break;
+            }
+
+            let component =
+                Component::new(rng.random_range(2..=max_w), rng.random_range(2..=max_h));
+            let x = rng.random_range(0..=width - component.width);
+            let y = rng.random_range(0..=height - component.height);
+            let anchor = Anchor::new(x, y, component);
+
+            if res.iter().all(|other| !anchors_touch(&anchor, other)) {
+                res.push(anchor);
+            }
+        }
+
+        res
+    }
+}
+
+/// Components spread along the top-left to bottom-right diagonal, each pushed up to `spread` cells off of it
+#[derive(Clone, Copy, Debug)]
+pub struct DiagonalBand {
+    pub spread: u8,
+}
+
+impl AnchorStrategy for DiagonalBand {
+    fn place_anchors(&self, width: u8, height: u8, rng: &mut dyn RngCore) -> Vec<Anchor> {
+        let component = Component::new(2.max(width / 5), 2.max(height / 5));
+        let (max_x, max_y) = (width - component.width, height - component.height);
+
+        // Leave at least one cell between components along the diagonal
+        let steps = (width / (component.width + 1))
+            .min(height / (component.height + 1))
+            .max(2);
+
+        let spread = self.spread as i16;
+
+        (0..steps)
+            .map(|i| {
+                let x = (max_x as u16 * i as u16 / (steps - 1) as u16) as i16;
+                let y = (max_y as u16 * i as u16 / (steps - 1) as u16) as i16;
+                let offset = rng.random_range(-spread..=spread);
+
+                Anchor::new(
+                    (x + offset).clamp(0, max_x as i16) as u8,
+                    (y - offset).clamp(0, max_y as i16) as u8,
+                    component,
+                )
+            })
+            .collect()
+    }
+}
+
+/// A caller-supplied list of anchors, used as-is. Anchors smaller than 2x2 or that don't fit on the grid are skipped
+#[derive(Clone, Debug)]
+pub struct FixedAnchors(pub Vec<Anchor>);
+
+impl AnchorStrategy for FixedAnchors {
+    fn place_anchors(&self, _width: u8, _height: u8, _rng: &mut dyn RngCore) -> Vec<Anchor> {
+        self.0.clone()
+    }
+}
+
+/// Whether the two anchors overlap or are directly adjacent
+fn anchors_touch(a: &Anchor, b: &Anchor) -> bool {
+    let (ax, ay) = (a.x as i16, a.y as i16);
+    let (bx, by) = (b.x as i16, b.y as i16);
+
+    ax <= bx + b.component.width as i16
+        && bx <= ax + a.component.width as i16
+        && ay <= by + b.component.height as i16
+        && by <= ay + a.component.height as i16
+}
 
 pub fn generate_anchors(
     (grid_w, grid_h): (u8, u8),
     (comp_w, comp_h): (u8, u8),
-    (count_v, count_h): (u8, u8),
+    (count_h, count_v): (u8, u8),
 ) -> Vec<Anchor> {
     if comp_w * count_h + (count_h - 1) > grid_w || comp_h * count_v + (count_v - 1) > grid_h {
         panic!("Invalid generate_anchors call");
@@ -55,11 +167,11 @@ pub fn generate_anchors(
     let xl = xs.len();
     let yl = ys.len();
 
-    if xs[xl - 1] + comp_h != grid_h {
-        xs[xl - 1] = grid_h - comp_h;
+    if xs[xl - 1] + comp_w != grid_w {
+        xs[xl - 1] = grid_w - comp_w;
     }
-    if ys[yl - 1] + comp_w != grid_w {
-        ys[yl - 1] = grid_w - comp_w;
+    if ys[yl - 1] + comp_h != grid_h {
+        ys[yl - 1] = grid_h - comp_h;
     }
 
     let mut res: Vec<Anchor> = Vec::new();
@@ -75,8 +187,46 @@ pub fn generate_anchors(
 
 #[cfg(test)]
 mod tests {
+    use rand::rng;
+
     use super::*;
 
+    const ANCHORS_8X8_1: [Anchor; 4] = [
+        Anchor::new(0, 0, Component::new(2, 2)),
+        Anchor::new(6, 0, Component::new(2, 2)),
+        Anchor::new(0, 6, Component::new(2, 2)),
+        Anchor::new(6, 6, Component::new(2, 2)),
+    ];
+    //const ANCHORS_8X8: [Anchor; 4] = [
+    //    Anchor::new(0, 0, Component::new(2, 3)),
+    //    Anchor::new(5, 0, Component::new(3, 2)),
+    //    Anchor::new(0, 6, Component::new(3, 2)),
+    //    Anchor::new(6, 5, Component::new(2, 3)),
+    //];
+    const ANCHORS_8X8_2: [Anchor; 9] = [
+        Anchor::new(0, 0, Component::new(2, 2)),
+        Anchor::new(3, 0, Component::new(2, 2)),
+        Anchor::new(6, 0, Component::new(2, 2)),
+        Anchor::new(0, 3, Component::new(2, 2)),
+        Anchor::new(3, 3, Component::new(2, 2)),
+        Anchor::new(6, 3, Component::new(2, 2)),
+        Anchor::new(0, 6, Component::new(2, 2)),
+        Anchor::new(3, 6, Component::new(2, 2)),
+        Anchor::new(6, 6, Component::new(2, 2)),
+    ];
+
+    const ANCHORS_20X20: [Anchor; 9] = [
+        Anchor::new(0, 0, Component::new(5, 5)),
+        Anchor::new(7, 0, Component::new(5, 5)),
+        Anchor::new(15, 0, Component::new(5, 5)),
+        Anchor::new(0, 7, Component::new(5, 5)),
+        Anchor::new(7, 7, Component::new(5, 5)),
+        Anchor::new(15, 7, Component::new(5, 5)),
+        Anchor::new(0, 15, Component::new(5, 5)),
+        Anchor::new(7, 15, Component::new(5, 5)),
+        Anchor::new(15, 15, Component::new(5, 5)),
+    ];
+
     #[test]
     fn test() {
         let anchors_8x8_1 = generate_anchors((8, 8), (2, 2), (2, 2));
@@ -87,4 +237,67 @@ mod tests {
         assert_eq!(anchors_8x8_2, ANCHORS_8X8_2);
         assert_eq!(anchors_20x20, ANCHORS_20X20);
     }
+
+    #[test]
+    fn test_lattice_matches_tables() {
+        let rng = &mut rng();
+
+        assert_eq!(Lattice.place_anchors(8, 8, rng), ANCHORS_8X8_2);
+        assert_eq!(Lattice.place_anchors(20, 20, rng), ANCHORS_20X20);
+        assert_eq!(
+            FixedAnchors(ANCHORS_8X8_1.to_vec()).place_anchors(8, 8, rng),
+            ANCHORS_8X8_1
+        );
+    }
+
+    #[test]
+    fn test_lattice_non_square() {
+        let rng = &mut rng();
+
+        for (width, height) in [(20, 8), (6, 25), (30, 11)] {
+            let anchors = Lattice.place_anchors(width, height, rng);
+
+            assert!(anchors.iter().any(|a| a.x + a.component.width == width));
+            assert!(anchors.iter().any(|a| a.y + a.component.height == height));
+            for anchor in anchors {
+                assert!(anchor.x + anchor.component.width <= width);
+                assert!(anchor.y + anchor.component.height <= height);
+            }
+        }
+    }
+
+    #[test]
+    fn test_strategies_stay_in_bounds() {
+        let rng = &mut rng();
+        let strategies: [&dyn AnchorStrategy; 3] = [
+            &JitteredLattice { jitter: 2 },
+            &RandomScatter { count: 8 },
+            &DiagonalBand { spread: 3 },
+        ];
+
+        for strategy in strategies {
+            for _ in 0..50 {
+                for anchor in strategy.place_anchors(20, 15, rng) {
+                    assert!(anchor.x + anchor.component.width <= 20);
+                    assert!(anchor.y + anchor.component.height <= 15);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_scatter_no_overlap() {
+        let rng = &mut rng();
+
+        for _ in 0..50 {
+            let anchors = RandomScatter { count: 10 }.place_anchors(20, 20, rng);
+
+            assert!(!anchors.is_empty());
+            for (i, a) in anchors.iter().enumerate() {
+                for b in &anchors[i + 1..] {
+                    assert!(!anchors_touch(a, b));
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,358 @@
+use std::collections::VecDeque;
+
+use crate::{
+    combinations::{DigitSet, combinations},
+    puzzle::Puzzle,
+};
+
+/// Digits each empty cell can still hold, given the digits placed so far
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidates {
+    cells: Vec<Vec<DigitSet>>,
+    empty: Vec<Vec<bool>>,
+    contradiction: bool,
+}
+
+impl Candidates {
+    /// Candidates of an empty cell, the empty set for anything else
+    pub fn get(&self, x: u8, y: u8) -> DigitSet {
+        if self.is_empty_cell(x, y) {
+            self.cells[y as usize][x as usize]
+        } else {
+            DigitSet::EMPTY
+        }
+    }
+
+    /// Candidates in the layout of `Cell.notes` in the game, index 0 being digit 1
+    pub fn notes(&self, x: u8, y: u8) -> [bool; 9] {
+        let set = self.get(x, y);
+
+        std::array::from_fn(|i| set.contains(i as u8 + 1))
+    }
+
+    /// Whether the placed digits can't be part of a solution, in which case the candidates are incomplete
+    pub fn has_contradiction(&self) -> bool {
+        self.contradiction
+    }
+
+    fn is_empty_cell(&self, x: u8, y: u8) -> bool {
+        self.empty
+            .get(y as usize)
+            .and_then(|r| r.get(x as usize))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Computes the candidates of every empty cell. `digits` has one digit per cell of the puzzle, 0 for empty cells
+pub fn compute_candidates(puzzle: &Puzzle, digits: &[Vec<u8>]) -> Candidates {
+    let digit_at = |x: u8, y: u8| {
+        digits
+            .get(y as usize)
+            .and_then(|r| r.get(x as usize))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let mut empty = vec![vec![false; puzzle.width as usize]; puzzle.height as usize];
+    let mut cells = vec![vec![DigitSet::EMPTY; puzzle.width as usize]; puzzle.height as usize];
+
+    for (x, y) in puzzle.fillable_cells() {
+        let digit = digit_at(x, y);
+
+        empty[y as usize][x as usize] = digit == 0;
+        cells[y as usize][x as usize] = if digit == 0 {
+            DigitSet::ALL
+        } else {
+            DigitSet::single(digit)
+        };
+    }
+
+    let contradiction = !propagate(puzzle, &RuleIndex::new(puzzle), &mut cells);
+
+    Candidates {
+        cells,
+        empty,
+        contradiction,
+    }
+}
+
+/// Rules going through each cell, so propagation only looks again at rules next to a change. Worked out once per
+/// puzzle
+#[derive(Clone, Debug)]
+pub(crate) struct RuleIndex {
+    rules_at: Vec<Vec<Vec<usize>>>,
+}
+
+impl RuleIndex {
+    pub(crate) fn new(puzzle: &Puzzle) -> Self {
+        let mut rules_at = vec![vec![Vec::new(); puzzle.width as usize]; puzzle.height as usize];
+        for (i, rule) in puzzle.rules.iter().enumerate() {
+            for (x, y) in rule.cells() {
+                rules_at[y as usize][x as usize].push(i);
+            }
+        }
+
+        Self { rules_at }
+    }
+
+    pub(crate) fn at(&self, x: u8, y: u8) -> &[usize] {
+        &self.rules_at[y as usize][x as usize]
+    }
+}
+
+/// Narrows every cell's candidates using its rules until nothing changes. Placed digits are single candidates.
+/// Returns false if some cell ends up with no candidates
+pub(crate) fn propagate(puzzle: &Puzzle, index: &RuleIndex, cells: &mut [Vec<DigitSet>]) -> bool {
+    propagate_rules(puzzle, index, cells, (0..puzzle.rules.len()).collect())
+}
+
+/// Same as `propagate` after only the cell at `(x, y)` changed, with every rule having been narrowed before
+pub(crate) fn propagate_cell(
+    puzzle: &Puzzle,
+    index: &RuleIndex,
+    cells: &mut [Vec<DigitSet>],
+    x: u8,
+    y: u8,
+) -> bool {
+    propagate_rules(
+        puzzle,
+        index,
+        cells,
+        index.at(x, y).iter().copied().collect(),
+    )
+}
+
+fn propagate_rules(
+    puzzle: &Puzzle,
+    index: &RuleIndex,
+    cells: &mut [Vec<DigitSet>],
+    start: VecDeque<usize>,
+) -> bool {
+    let mut queue = start;
+    let mut queued = vec![false; puzzle.rules.len()];
+    for &i in &queue {
+        queued[i] = true;
+    }
+
+    while let Some(i) = queue.pop_front() {
+        queued[i] = false;
+        let rule = &puzzle.rules[i];
+
+        let sets: Vec<DigitSet> = rule
+            .cells()
+            .map(|(x, y)| cells[y as usize][x as usize])
+            .collect();
+
+        let restricted = restrict_rule(rule.value, &sets);
+
+        for ((x, y), (new, old)) in rule.cells().zip(restricted.into_iter().zip(sets)) {
+            if new == old {
+                continue;
+            }
+
+            if new.is_empty() {
+                return false;
+            }
+
+            cells[y as usize][x as usize] = new;
+
+            for &j in index.at(x, y) {
+                if !queued[j] {
+                    queued[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+
+    true
+}
+
+/// Narrows the candidates of one rule's cells to digits that appear in some complete placement of the rule. A value
+/// of 0 means the sum isn't known, so only repeats are ruled out
+pub(crate) fn restrict_rule(value: u8, cells: &[DigitSet]) -> Vec<DigitSet> {
+    if value == 0 {
+        // Only digits already decided elsewhere in the rule are ruled out
+        return cells
+            .iter()
+            .enumerate()
+            .map(|(i, &set)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, other)| j != i && other.len() == 1)
+                    .fold(set, |acc, (_, &other)| acc.difference(other))
+            })
+            .collect();
+    }
+
+    let layers = used_layers(value, cells);
+    let n = cells.len();
+
+    // Keep a digit if it's on the way from no cells to a full assignment adding up to the value
+    let mut useful = [false; 1024];
+    for &used in &layers[n] {
+        useful[used as usize] = SET_SUMS[used as usize] == value;
+    }
+
+    let mut res = vec![DigitSet::EMPTY; n];
+
+    for i in (0..n).rev() {
+        let mut before = [false; 1024];
+        let mut kept = 0;
+
+        for &used in &layers[i] {
+            for flag in flags(cells[i].bits() & !used) {
+                if useful[(used | flag) as usize] {
+                    before[used as usize] = true;
+                    kept |= flag;
+                }
+            }
+        }
+
+        res[i] = DigitSet::from_bits(kept);
+        useful = before;
+    }
+
+    res
+}
+
+/// Combinations making up `value` that can still be placed in the rule's cells, each cell getting a different digit
+pub(crate) fn fitting_combinations(value: u8, cells: &[DigitSet]) -> Vec<DigitSet> {
+    let mut full = [false; 1024];
+    for used in used_layers(value, cells).pop().unwrap_or_default() {
+        full[used as usize] = true;
+    }
+
+    combinations(cells.len() as u8, value)
+        .iter()
+        .copied()
+        .filter(|c| full[c.bits() as usize])
+        .collect()
+}
+
+/// Sum of the digits in each set, indexed by its bits
+const SET_SUMS: [u8; 1024] = {
+    let mut sums = [0; 1024];
+    let mut bits = 0;
+
+    while bits < 1024 {
+        let mut digit = 1;
+        while digit <= 9 {
+            if bits & (1 << digit) != 0 {
+                sums[bits] += digit as u8;
+            }
+            digit += 1;
+        }
+        bits += 1;
+    }
+
+    sums
+};
+
+/// Each set bit on its own, lowest first
+fn flags(mut bits: u16) -> impl Iterator<Item = u16> {
+    std::iter::from_fn(move || {
+        (bits != 0).then(|| {
+            let low = bits & bits.wrapping_neg();
+            bits ^= low;
+            low
+        })
+    })
+}
+
+/// Digits used by some assignment of the first `i` cells that doesn't add up to more than `value`, for each `i`, as
+/// the bits of their sets
+fn used_layers(value: u8, cells: &[DigitSet]) -> Vec<Vec<u16>> {
+    let mut layers = vec![vec![0]];
+
+    for (i, &set) in cells.iter().enumerate() {
+        let mut seen = [false; 1024];
+        let mut next = Vec::new();
+
+        for &used in &layers[i] {
+            for flag in flags(set.bits() & !used) {
+                let mask = used | flag;
+
+                if SET_SUMS[mask as usize] <= value && !seen[mask as usize] {
+                    seen[mask as usize] = true;
+                    next.push(mask);
+                }
+            }
+        }
+
+        layers.push(next);
+    }
+
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::fixtures::unsolved;
+
+    #[test]
+    fn test_compute_candidates() {
+        let puzzle = unsolved();
+
+        // 16 and 17 in two cells leave only 9 for the shared cell, which decides the rest
+        let empty = vec![vec![0; 4]; 3];
+        let candidates = compute_candidates(&puzzle, &empty);
+
+        assert_eq!(candidates.get(1, 1), DigitSet::single(9));
+        assert_eq!(candidates.get(2, 1), DigitSet::single(7));
+        assert_eq!(candidates.get(1, 2), DigitSet::single(8));
+        assert_eq!(candidates.get(2, 2), DigitSet::single(4));
+        assert_eq!(candidates.get(0, 0), DigitSet::EMPTY);
+        assert_eq!(
+            candidates.notes(2, 2),
+            [false, false, false, true, false, false, false, false, false]
+        );
+        assert!(!candidates.has_contradiction());
+
+        // Placed digits have no candidates. 8 can't be part of 16 in two cells
+        let wrong = vec![vec![0; 4], vec![0, 8, 0, 0], vec![0; 4]];
+        let candidates = compute_candidates(&puzzle, &wrong);
+
+        assert_eq!(candidates.get(1, 1), DigitSet::EMPTY);
+        assert!(candidates.has_contradiction());
+    }
+
+    #[test]
+    fn test_restrict_rule() {
+        let all = DigitSet::ALL;
+
+        // 3 cells adding to 7 can only be 1, 2 and 4
+        assert_eq!(
+            restrict_rule(7, &[all, all, all]),
+            vec![DigitSet::from_iter([1, 2, 4]); 3]
+        );
+
+        // With the first cell known to be 4, the others must be 1 or 2
+        assert_eq!(
+            restrict_rule(7, &[DigitSet::single(4), all, all]),
+            vec![
+                DigitSet::single(4),
+                DigitSet::from_iter([1, 2]),
+                DigitSet::from_iter([1, 2])
+            ]
+        );
+
+        // 10 in two cells with the second one limited to 6-9
+        assert_eq!(
+            restrict_rule(10, &[all, DigitSet::from_iter([6, 7, 8, 9])]),
+            vec![
+                DigitSet::from_iter([1, 2, 3, 4]),
+                DigitSet::from_iter([6, 7, 8, 9])
+            ]
+        );
+
+        // Unknown value only removes decided digits
+        assert_eq!(
+            restrict_rule(0, &[DigitSet::single(3), all]),
+            vec![DigitSet::single(3), all.without(3)]
+        );
+    }
+}
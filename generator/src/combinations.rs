@@ -0,0 +1,256 @@
+//! Which sets of distinct digits make up a run of a given length and sum.
+//!
+//! Every table is computed once on first use and shared afterwards. Lengths outside of 1-9 and sums outside of
+//! what that length can reach have no combinations.
+
+use std::{fmt::Display, sync::LazyLock};
+
+/// A set of the digits 1-9
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DigitSet(u16);
+
+impl DigitSet {
+    pub const EMPTY: Self = Self(0);
+    pub const ALL: Self = Self(0b11_1111_1110);
+
+    pub fn single(digit: u8) -> Self {
+        Self::EMPTY.with(digit)
+    }
+
+    pub fn contains(self, digit: u8) -> bool {
+        (1..=9).contains(&digit) && self.0 & (1 << digit) != 0
+    }
+
+    /// Copy of this set with `digit` added, digits outside of 1-9 are ignored
+    pub fn with(self, digit: u8) -> Self {
+        if (1..=9).contains(&digit) {
+            Self(self.0 | 1 << digit)
+        } else {
+            self
+        }
+    }
+
+    pub fn without(self, digit: u8) -> Self {
+        self.difference(Self::single(digit))
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn sum(self) -> u8 {
+        self.iter().sum()
+    }
+
+    pub fn is_subset(self, other: Self) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// The set as flags, bit `d` for digit `d`
+    pub(crate) fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub(crate) fn from_bits(bits: u16) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// Digits in ascending order
+    pub fn iter(self) -> impl Iterator<Item = u8> {
+        (1..=9).filter(move |&d| self.contains(d))
+    }
+}
+
+impl FromIterator<u8> for DigitSet {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        iter.into_iter().fold(Self::EMPTY, Self::with)
+    }
+}
+
+impl Display for DigitSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for digit in self.iter() {
+            write!(f, "{}", digit)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Smallest sum a run of `len` distinct digits can have, `None` unless `len` is 1-9
+pub const fn min_sum(len: u8) -> Option<u8> {
+    if len == 0 || len > 9 {
+        return None;
+    }
+
+    Some(len * (len + 1) / 2)
+}
+
+/// Largest sum a run of `len` distinct digits can have, `None` unless `len` is 1-9
+pub const fn max_sum(len: u8) -> Option<u8> {
+    if len == 0 || len > 9 {
+        return None;
+    }
+
+    // 9 + 8 + ... + (10 - len)
+    Some(len * (19 - len) / 2)
+}
+
+#[derive(Default)]
+struct Entry {
+    sets: Vec<DigitSet>,
+    mandatory: DigitSet,
+    possible: DigitSet,
+}
+
+// Indexed by [len][sum]
+static TABLE: LazyLock<Vec<Vec<Entry>>> = LazyLock::new(|| {
+    let mut table: Vec<Vec<Entry>> = (0..=9)
+        .map(|_| (0..=45).map(|_| Entry::default()).collect())
+        .collect();
+
+    // Every non-empty subset of 1-9, in ascending order so each entry's sets are too
+    let mut subsets: Vec<DigitSet> = (1..512u16).map(|bits| DigitSet(bits << 1)).collect();
+    subsets.sort_by_key(|s| s.iter().collect::<Vec<_>>());
+
+    for set in subsets {
+        table[set.len() as usize][set.sum() as usize].sets.push(set);
+    }
+
+    for entry in table.iter_mut().flatten() {
+        if let Some(&first) = entry.sets.first() {
+            entry.mandatory = entry.sets.iter().fold(first, |acc, &s| acc.intersection(s));
+            entry.possible = entry
+                .sets
+                .iter()
+                .fold(DigitSet::EMPTY, |acc, &s| acc.union(s));
+        }
+    }
+
+    table
+});
+
+fn entry(len: u8, sum: u8) -> Option<&'static Entry> {
+    TABLE.get(len as usize)?.get(sum as usize)
+}
+
+/// Every set of `len` distinct digits adding up to `sum`
+pub fn combinations(len: u8, sum: u8) -> &'static [DigitSet] {
+    entry(len, sum).map_or(&[], |e| &e.sets)
+}
+
+/// Digits in every combination, empty if there are none
+pub fn mandatory_digits(len: u8, sum: u8) -> DigitSet {
+    entry(len, sum).map_or(DigitSet::EMPTY, |e| e.mandatory)
+}
+
+/// Digits in at least one combination
+pub fn possible_digits(len: u8, sum: u8) -> DigitSet {
+    entry(len, sum).map_or(DigitSet::EMPTY, |e| e.possible)
+}
+
+/// Digits in no combination
+pub fn impossible_digits(len: u8, sum: u8) -> DigitSet {
+    DigitSet::ALL.difference(possible_digits(len, sum))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All non-empty sets of distinct digits, as plain vectors
+    fn brute_force(len: u8, sum: u8) -> Vec<Vec<u8>> {
+        (1..512u32)
+            .map(|bits| {
+                (1..=9)
+                    .filter(|d| bits & (1 << (d - 1)) != 0)
+                    .collect::<Vec<u8>>()
+            })
+            .filter(|set| set.len() == len as usize && set.iter().sum::<u8>() == sum)
+            .collect()
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        for len in 0..=10 {
+            for sum in 0..=50 {
+                let expected = brute_force(len, sum);
+                let found: Vec<Vec<u8>> = combinations(len, sum)
+                    .iter()
+                    .map(|s| s.iter().collect())
+                    .collect();
+
+                let mut sorted = expected.clone();
+                sorted.sort();
+                assert_eq!(found, sorted, "len {} sum {}", len, sum);
+
+                let in_all =
+                    |d: u8| !expected.is_empty() && expected.iter().all(|s| s.contains(&d));
+                let in_any = |d: u8| expected.iter().any(|s| s.contains(&d));
+
+                for d in 1..=9 {
+                    assert_eq!(mandatory_digits(len, sum).contains(d), in_all(d));
+                    assert_eq!(possible_digits(len, sum).contains(d), in_any(d));
+                    assert_eq!(impossible_digits(len, sum).contains(d), !in_any(d));
+                }
+
+                if let (Some(min), Some(max)) = (min_sum(len), max_sum(len)) {
+                    let reachable = (min..=max).contains(&sum);
+                    assert_eq!(!expected.is_empty(), reachable, "len {} sum {}", len, sum);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_combinations() {
+        assert_eq!(combinations(2, 3), [DigitSet::from_iter([1, 2])]);
+        assert_eq!(combinations(2, 17), [DigitSet::from_iter([8, 9])]);
+        assert_eq!(combinations(9, 45), [DigitSet::ALL]);
+        assert_eq!(combinations(3, 7), [DigitSet::from_iter([1, 2, 4])]);
+        assert_eq!(combinations(2, 10).len(), 4);
+        assert_eq!(mandatory_digits(4, 12), DigitSet::from_iter([1, 2]));
+        assert_eq!(impossible_digits(2, 4).to_string(), "2456789");
+        assert_eq!((min_sum(3), max_sum(3)), (Some(6), Some(24)));
+    }
+
+    #[test]
+    fn test_sum_bounds() {
+        assert_eq!((min_sum(1), max_sum(1)), (Some(1), Some(9)));
+        assert_eq!((min_sum(9), max_sum(9)), (Some(45), Some(45)));
+
+        for len in [0, 10, 20, 30, 255] {
+            assert_eq!((min_sum(len), max_sum(len)), (None, None), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_digit_set() {
+        let set = DigitSet::from_iter([3, 1, 9, 0, 10]);
+
+        assert_eq!(set.to_string(), "139");
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.sum(), 13);
+        assert!(set.without(3).is_subset(set));
+        assert!(!set.contains(0));
+        assert_eq!(set.without(0), set);
+        assert_eq!(DigitSet::single(5).union(set).len(), 4);
+        assert_eq!(DigitSet::ALL.difference(set).len(), 6);
+    }
+}
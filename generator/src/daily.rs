@@ -0,0 +1,288 @@
+//! The puzzle of the day, the same for every player without a server.
+//!
+//! A date and an optional salt hash to a seed, and the day of the week picks the difficulty, easiest on Mondays and
+//! hardest on weekends. Puzzles are generated from that seed and the ones after it until the solver grades one as
+//! right for the difficulty.
+//!
+//! The seed includes `GENERATOR_VERSION`, so a release that changes generation changes every day's puzzle at once
+//! rather than some of them silently. That includes past dates: after any version bump, a date gives a different
+//! puzzle than it did before, so saved daily puzzles should keep the puzzle itself and the version it was made with.
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{GenError, ParseError},
+    fingerprint::fnv1a,
+    grid_gen::{GENERATOR_VERSION, GenConfig, generate_puzzle},
+    puzzle::Puzzle,
+    solver::{Technique, grade},
+};
+
+// Most sizes and difficulties take a few candidates, this only stops a config that can't make the right kind at all
+const MAX_GRADE_ATTEMPTS: u64 = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+/// A calendar date, independent of time zones
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// `None` if the day doesn't exist
+    pub fn new(year: i32, month: u8, day: u8) -> Option<Self> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+
+        (1..=days_in_month)
+            .contains(&day)
+            .then_some(Self { year, month, day })
+    }
+
+    pub fn year(self) -> i32 {
+        self.year
+    }
+
+    pub fn month(self) -> u8 {
+        self.month
+    }
+
+    pub fn day(self) -> u8 {
+        self.day
+    }
+
+    /// Days since 1970-01-01, negative before it
+    fn days_since_epoch(self) -> i64 {
+        // Counting years from March puts the leap day at the end of the year
+        let y = self.year as i64 - (self.month <= 2) as i64;
+        let era = y.div_euclid(400);
+        let year_of_era = y - era * 400;
+        let month = (self.month as i64 + 9) % 12;
+        let day_of_year = (153 * month + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    pub fn weekday(self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        match (self.days_since_epoch() + 3).rem_euclid(7) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Parses `YYYY-MM-DD`
+impl FromStr for Date {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::new(0, format!("'{}' isn't a YYYY-MM-DD date", s));
+
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (next()?, next()?, next()?);
+
+        Date::new(
+            year.parse().map_err(|_| invalid())?,
+            month.parse().map_err(|_| invalid())?,
+            day.parse().map_err(|_| invalid())?,
+        )
+        .ok_or_else(invalid)
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// How hard a puzzle is: the size of the board and the techniques the solver needs for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    /// Width and height of the grid before the clue row and column are added
+    pub fn size(self) -> u8 {
+        match self {
+            Self::Easy => 8,
+            Self::Medium => 10,
+            Self::Hard => 12,
+            Self::Expert => 15,
+        }
+    }
+
+    /// Whether a puzzle the solver grades as `grade` fits this difficulty, `None` meaning it needs guessing. Easy and
+    /// medium puzzles never need contradictions, hard ones do, and expert ones need contradictions or guessing
+    pub fn accepts(self, grade: Option<Technique>) -> bool {
+        match self {
+            Self::Easy | Self::Medium => grade.is_some_and(|t| t < Technique::Contradiction),
+            Self::Hard => grade == Some(Technique::Contradiction),
+            Self::Expert => grade.is_none_or(|t| t == Technique::Contradiction),
+        }
+    }
+
+    /// The difficulty schedule: easy on Mondays, getting harder through the week, hardest on weekends
+    pub fn for_weekday(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Monday => Self::Easy,
+            Weekday::Tuesday | Weekday::Wednesday => Self::Medium,
+            Weekday::Thursday | Weekday::Friday => Self::Hard,
+            Weekday::Saturday | Weekday::Sunday => Self::Expert,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyPuzzle {
+    pub date: Date,
+    /// `GENERATOR_VERSION` the puzzle was made with
+    pub generator_version: u32,
+    /// Seed of the date, the first candidate's
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub puzzle: Puzzle,
+}
+
+/// Seed of the daily puzzle for `date`. A salt gives a separate sequence of puzzles, for example per game edition
+pub fn daily_seed(date: Date, salt: Option<&str>) -> u64 {
+    let key = format!(
+        "daily/{}/{}/{}",
+        GENERATOR_VERSION,
+        date,
+        salt.unwrap_or_default()
+    );
+
+    fnv1a(key.as_bytes())
+}
+
+/// Generates the puzzle of the day for `date`
+pub fn daily_puzzle(date: Date, salt: Option<&str>) -> Result<DailyPuzzle, GenError> {
+    let seed = daily_seed(date, salt);
+    let difficulty = Difficulty::for_weekday(date.weekday());
+    let size = difficulty.size();
+
+    let puzzle = generate_graded(&GenConfig::new(size, size).with_seed(seed), difficulty)?;
+
+    Ok(DailyPuzzle {
+        date,
+        generator_version: GENERATOR_VERSION,
+        seed,
+        difficulty,
+        puzzle,
+    })
+}
+
+/// Generates puzzles until the solver grades one as right for `difficulty`. A seeded config moves on to the next seed
+/// for each candidate, so the result is still repeatable
+pub fn generate_graded(config: &GenConfig, difficulty: Difficulty) -> Result<Puzzle, GenError> {
+    for attempt in 0..MAX_GRADE_ATTEMPTS {
+        let mut config = config.clone();
+        config.seed = config.seed.map(|seed| seed.wrapping_add(attempt));
+
+        let puzzle = generate_puzzle(&config)?;
+        if difficulty.accepts(grade(&puzzle)) {
+            return Ok(puzzle);
+        }
+    }
+
+    Err(GenError::AttemptsExhausted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::{Fingerprint, fingerprint};
+
+    #[test]
+    fn test_date() {
+        let date: Date = "2026-10-19".parse().unwrap();
+
+        assert_eq!(date, Date::new(2026, 10, 19).unwrap());
+        assert_eq!(date.to_string(), "2026-10-19");
+        assert_eq!(date.weekday(), Weekday::Monday);
+        assert_eq!(Date::new(2000, 2, 29).unwrap().weekday(), Weekday::Tuesday);
+        assert_eq!(
+            Date::new(1969, 12, 31).unwrap().weekday(),
+            Weekday::Wednesday
+        );
+
+        assert_eq!(Date::new(1900, 2, 29), None);
+        assert_eq!(Date::new(2026, 13, 1), None);
+        assert!("2026-02-30".parse::<Date>().is_err());
+        assert!("yesterday".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn test_daily_puzzle() {
+        let monday = Date::new(2026, 10, 19).unwrap();
+        let saturday = Date::new(2026, 10, 24).unwrap();
+
+        let daily = daily_puzzle(monday, None).unwrap();
+        assert_eq!(daily.difficulty, Difficulty::Easy);
+        assert_eq!(daily.generator_version, GENERATOR_VERSION);
+        assert_eq!(daily_puzzle(monday, None).unwrap(), daily);
+        assert_ne!(daily_seed(monday, Some("edition")), daily.seed);
+
+        let weekend = daily_puzzle(saturday, None).unwrap();
+        assert!(weekend.puzzle.width > daily.puzzle.width);
+        assert!(Difficulty::Easy.accepts(grade(&daily.puzzle)));
+        assert!(Difficulty::Expert.accepts(grade(&weekend.puzzle)));
+        assert!(!Difficulty::Hard.accepts(None));
+        assert!(!Difficulty::Medium.accepts(Some(Technique::Contradiction)));
+
+        // Changing this means every player gets a different puzzle than before, which needs a new GENERATOR_VERSION
+        assert_eq!(
+            fingerprint(&daily.puzzle),
+            Fingerprint(0x31fa_90e3_2d69_eeb6)
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::{error::Error, fmt::Display, io};
+
+/// Reasons grid generation can fail
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenError {
+    UnsupportedSize {
+        width: u8,
+        height: u8,
+    },
+    MaskSizeMismatch {
+        expected: (u8, u8),
+        found: (u8, u8),
+    },
+    /// The mask has no room for a connected grid with runs of length 2-9
+    MaskUnusable,
+    AttemptsExhausted,
+    /// The config's time limit ran out
+    TimedOut,
+    /// The config's cancel token was cancelled
+    Cancelled,
+}
+
+impl Display for GenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedSize { width, height } => {
+                write!(f, "{}x{} size is unsupported", width, height)
+            }
+            Self::MaskSizeMismatch { expected, found } => write!(
+                f,
+                "Mask is {}x{} but the grid is {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            Self::MaskUnusable => write!(
+                f,
+                "Mask can't produce a connected grid where every run is 2-9 cells long"
+            ),
+            Self::AttemptsExhausted => write!(f, "Unable to generate a valid grid"),
+            Self::TimedOut => write!(f, "Generation ran out of time"),
+            Self::Cancelled => write!(f, "Generation was cancelled"),
+        }
+    }
+}
+
+impl Error for GenError {}
+
+/// Reasons loading a mask from an image can fail
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    InvalidPbm(String),
+    /// Only PNG and PBM images are supported
+    UnknownFormat,
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Unable to read image: {}", e),
+            Self::Png(e) => write!(f, "Invalid PNG: {}", e),
+            Self::InvalidPbm(reason) => write!(f, "Invalid PBM: {}", reason),
+            Self::UnknownFormat => write!(f, "Image must be a PNG or PBM file"),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+impl From<io::Error> for ImageError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<png::DecodingError> for ImageError {
+    fn from(value: png::DecodingError) -> Self {
+        Self::Png(value)
+    }
+}
+
+/// Error from reading one of the text formats in `text`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line the error was found on, 0 if it isn't tied to a line
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Reasons reading a save can fail
+#[derive(Debug)]
+pub enum SaveError {
+    Json(serde_json::Error),
+    MissingVersion,
+    /// Saved by a newer version of the game than this one
+    UnsupportedVersion(u32),
+    /// The digits don't cover the embedded puzzle's board
+    SizeMismatch,
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "Invalid save: {}", e),
+            Self::MissingVersion => write!(f, "Save has no version"),
+            Self::UnsupportedVersion(v) => write!(f, "Save version {} is newer than supported", v),
+            Self::SizeMismatch => write!(f, "Save doesn't match the size of its puzzle"),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// Reasons decoding a share code can fail
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShareCodeError {
+    /// Not valid URL-safe base64
+    InvalidEncoding,
+    /// Mistyped or cut-off code
+    ChecksumMismatch,
+    UnsupportedVersion(u8),
+    /// The checksum matches but the contents don't describe a puzzle
+    InvalidPuzzle(String),
+}
+
+impl Display for ShareCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidEncoding => write!(f, "Share code contains invalid characters"),
+            Self::ChecksumMismatch => write!(f, "Share code is mistyped or incomplete"),
+            Self::UnsupportedVersion(v) => write!(f, "Share code version {} is unsupported", v),
+            Self::InvalidPuzzle(reason) => write!(f, "Share code is invalid: {}", reason),
+        }
+    }
+}
+
+impl Error for ShareCodeError {}
@@ -0,0 +1,185 @@
+use rand::{Rng, seq::SliceRandom};
+
+use crate::puzzle::{Puzzle, Rule};
+
+// Give up on a shape rather than searching forever, the caller can try another one
+const MAX_FILL_STEPS: usize = 200_000;
+
+/// Fills every fillable cell with a digit so no rule repeats one, then sets each rule's value from its digits.
+/// Returns `None` if no fill was found quickly enough
+pub fn fill_puzzle(puzzle: &Puzzle, rng: &mut impl Rng) -> Option<Puzzle> {
+    fill_around(puzzle, &[], rng)
+}
+
+/// Same as `fill_puzzle`, but keeps the digits of `puzzle`'s solution outside of `cells`, which get new random ones
+pub(crate) fn refill(puzzle: &Puzzle, cells: &[(u8, u8)], rng: &mut impl Rng) -> Option<Puzzle> {
+    let mut digits = puzzle.solution.clone().unwrap_or_default();
+    for &(x, y) in cells {
+        if let Some(digit) = digits
+            .get_mut(y as usize)
+            .and_then(|r| r.get_mut(x as usize))
+        {
+            *digit = 0;
+        }
+    }
+
+    fill_around(puzzle, &digits, rng)
+}
+
+/// Fills the cells that don't have a digit in `digits` yet, missing rows and cells counting as empty
+fn fill_around(puzzle: &Puzzle, digits: &[Vec<u8>], rng: &mut impl Rng) -> Option<Puzzle> {
+    let cells: Vec<(u8, u8)> = puzzle.fillable_cells().collect();
+
+    // Index of the across and down rule of each cell
+    let cell_rules: Vec<(usize, usize)> = cells
+        .iter()
+        .map(|&(x, y)| {
+            let find = |is_vertical: bool| {
+                puzzle
+                    .rules
+                    .iter()
+                    .position(|r| r.is_vertical == is_vertical && r.cells().any(|c| c == (x, y)))
+                    .expect("Fillable cell without a rule")
+            };
+
+            (find(false), find(true))
+        })
+        .collect();
+
+    let mut filler = Filler {
+        cell_rules,
+        digits: vec![0; cells.len()],
+        // Bit n set means digit n is already used in the rule
+        used: vec![0; puzzle.rules.len()],
+        steps: 0,
+    };
+
+    for (i, &(x, y)) in cells.iter().enumerate() {
+        let digit = digits
+            .get(y as usize)
+            .and_then(|r| r.get(x as usize))
+            .copied()
+            .unwrap_or(0);
+
+        if digit != 0 {
+            let (across, down) = filler.cell_rules[i];
+            filler.digits[i] = digit;
+            filler.used[across] |= 1 << digit;
+            filler.used[down] |= 1 << digit;
+        }
+    }
+
+    if !filler.fill(rng) {
+        return None;
+    }
+
+    let mut solution = vec![vec![0; puzzle.width as usize]; puzzle.height as usize];
+    for (&(x, y), &digit) in cells.iter().zip(&filler.digits) {
+        solution[y as usize][x as usize] = digit;
+    }
+
+    let rules = puzzle
+        .rules
+        .iter()
+        .map(|r| Rule {
+            value: r
+                .cells()
+                .map(|(x, y)| solution[y as usize][x as usize])
+                .sum(),
+            ..*r
+        })
+        .collect();
+
+    Some(Puzzle::new(
+        puzzle.width,
+        puzzle.height,
+        rules,
+        Some(solution),
+    ))
+}
+
+struct Filler {
+    cell_rules: Vec<(usize, usize)>,
+    digits: Vec<u8>,
+    used: Vec<u16>,
+    steps: usize,
+}
+
+impl Filler {
+    fn available(&self, cell: usize) -> u16 {
+        let (across, down) = self.cell_rules[cell];
+
+        !(self.used[across] | self.used[down]) & 0b11_1111_1110
+    }
+
+    fn fill(&mut self, rng: &mut impl Rng) -> bool {
+        self.steps += 1;
+        if self.steps > MAX_FILL_STEPS {
+            return false;
+        }
+
+        // Fill the most constrained cell next, to find dead ends early
+        let Some(cell) = (0..self.digits.len())
+            .filter(|&i| self.digits[i] == 0)
+            .min_by_key(|&i| self.available(i).count_ones())
+        else {
+            return true;
+        };
+
+        let available = self.available(cell);
+        let mut options: Vec<u8> = (1..=9).filter(|d| available & (1 << d) != 0).collect();
+        options.shuffle(rng);
+
+        let (across, down) = self.cell_rules[cell];
+
+        for digit in options {
+            self.digits[cell] = digit;
+            self.used[across] |= 1 << digit;
+            self.used[down] |= 1 << digit;
+
+            if self.fill(rng) {
+                return true;
+            }
+
+            self.used[across] &= !(1 << digit);
+            self.used[down] &= !(1 << digit);
+        }
+
+        self.digits[cell] = 0;
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rng;
+
+    use super::*;
+    use crate::grid_gen::{GenConfig, generate_grid};
+
+    #[test]
+    fn test_fill_puzzle() {
+        let rng = &mut rng();
+
+        for _ in 0..10 {
+            let shape = generate_grid(&GenConfig::new(12, 12)).unwrap();
+            let puzzle = fill_puzzle(&Puzzle::from_shape(&shape), rng).unwrap();
+            let solution = puzzle.solution.as_ref().unwrap();
+
+            for rule in &puzzle.rules {
+                let digits: Vec<u8> = rule
+                    .cells()
+                    .map(|(x, y)| solution[y as usize][x as usize])
+                    .collect();
+
+                assert!(digits.iter().all(|d| (1..=9).contains(d)));
+                assert!((1..digits.len()).all(|i| !digits[..i].contains(&digits[i])));
+                assert_eq!(digits.iter().sum::<u8>(), rule.value);
+            }
+
+            // Round trips through the text format, which checks the solution against the rules
+            assert_eq!(puzzle.to_string().parse::<Puzzle>().unwrap(), puzzle);
+        }
+    }
+}
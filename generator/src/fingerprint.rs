@@ -0,0 +1,164 @@
+//! Identifies puzzles regardless of rotation and reflection.
+//!
+//! Flipping a kakuro board moves each clue to the other end of its run, and transposing it turns across runs into down
+//! runs, but the puzzle stays the same. The canonical form describes each inner cell by the sums of the runs through
+//! it, tries all 8 symmetries of the grid and keeps the smallest, so every variant of a puzzle ends up with the same
+//! form. Solutions aren't part of it.
+
+use std::fmt::Display;
+
+use crate::puzzle::Puzzle;
+
+/// Stable hash of a puzzle's canonical form, the same across releases and platforms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub u64);
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+// Marks cells that aren't fillable, which can't clash with a sum as those are at most 45
+const BLOCKED: u8 = 0xff;
+
+/// Across and down sum of every inner cell, leaving out the clue row and column
+fn cell_sums(puzzle: &Puzzle) -> Vec<Vec<(u8, u8)>> {
+    let mut sums =
+        vec![vec![(BLOCKED, BLOCKED); puzzle.width as usize - 1]; puzzle.height as usize - 1];
+
+    for rule in &puzzle.rules {
+        for (x, y) in rule.cells() {
+            let cell = &mut sums[y as usize - 1][x as usize - 1];
+
+            if rule.is_vertical {
+                cell.1 = rule.value;
+            } else {
+                cell.0 = rule.value;
+            }
+        }
+    }
+
+    sums
+}
+
+/// Width, height, then each cell's across and down sum row by row, for one symmetry of `sums`
+fn encode_symmetry(sums: &[Vec<(u8, u8)>], transpose: bool, flip_x: bool, flip_y: bool) -> Vec<u8> {
+    let (w, h) = (sums.first().map_or(0, |r| r.len()), sums.len());
+    let (out_w, out_h) = if transpose { (h, w) } else { (w, h) };
+
+    let mut bytes = vec![out_w as u8, out_h as u8];
+
+    for y in 0..out_h {
+        for x in 0..out_w {
+            let (sx, sy) = if transpose { (y, x) } else { (x, y) };
+            let sx = if flip_x { w - 1 - sx } else { sx };
+            let sy = if flip_y { h - 1 - sy } else { sy };
+
+            let (across, down) = sums[sy][sx];
+            if transpose {
+                bytes.extend([down, across]);
+            } else {
+                bytes.extend([across, down]);
+            }
+        }
+    }
+
+    bytes
+}
+
+/// The smallest encoding of the puzzle over all 8 symmetries. Equal for puzzles that are rotations or reflections of
+/// each other
+pub fn canonical_form(puzzle: &Puzzle) -> Vec<u8> {
+    if puzzle.width < 2 || puzzle.height < 2 {
+        return vec![0, 0];
+    }
+
+    let sums = cell_sums(puzzle);
+
+    [false, true]
+        .into_iter()
+        .flat_map(|t| [false, true].into_iter().map(move |fx| (t, fx)))
+        .flat_map(|(t, fx)| [false, true].into_iter().map(move |fy| (t, fx, fy)))
+        .map(|(t, fx, fy)| encode_symmetry(&sums, t, fx, fy))
+        .min()
+        .unwrap_or_default()
+}
+
+/// 64-bit FNV-1a, simple enough to stay the same forever
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// FNV-1a of the canonical form
+pub fn fingerprint(puzzle: &Puzzle) -> Fingerprint {
+    Fingerprint(fnv1a(&canonical_form(puzzle)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_gen::{GenConfig, generate_puzzle};
+
+    /// Rotates the puzzle a quarter turn clockwise, moving clues to the start of their new runs
+    fn rotate(puzzle: &Puzzle) -> Puzzle {
+        let sums = cell_sums(puzzle);
+        let (w, h) = (sums[0].len(), sums.len());
+
+        // Rotating clockwise: new (x, y) comes from old (y, h - 1 - x). Old down runs become across runs read
+        // backwards, old across runs become down runs
+        let shape: Vec<Vec<bool>> = (0..w)
+            .map(|y| (0..h).map(|x| sums[h - 1 - x][y].0 != BLOCKED).collect())
+            .collect();
+
+        let mut rotated = Puzzle::from_shape(&shape);
+        for rule in &mut rotated.rules {
+            let (x, y) = rule.cells().next().unwrap();
+            let (across, down) = sums[h - 1 - (x as usize - 1)][y as usize - 1];
+            rule.value = if rule.is_vertical { across } else { down };
+        }
+
+        rotated
+    }
+
+    fn mirror(puzzle: &Puzzle) -> Puzzle {
+        let sums = cell_sums(puzzle);
+        let w = sums[0].len();
+
+        let shape: Vec<Vec<bool>> = sums
+            .iter()
+            .map(|r| r.iter().rev().map(|c| c.0 != BLOCKED).collect())
+            .collect();
+
+        let mut mirrored = Puzzle::from_shape(&shape);
+        for rule in &mut mirrored.rules {
+            let (x, y) = rule.cells().next().unwrap();
+            let (across, down) = sums[y as usize - 1][w - x as usize];
+            rule.value = if rule.is_vertical { down } else { across };
+        }
+
+        mirrored
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let puzzle = generate_puzzle(&GenConfig::new(10, 10)).unwrap();
+        let print = fingerprint(&puzzle);
+
+        let mut variant = puzzle.clone();
+        for _ in 0..4 {
+            variant = rotate(&variant);
+            assert_eq!(fingerprint(&variant), print, "{}", variant);
+            assert_eq!(fingerprint(&mirror(&variant)), print);
+        }
+        assert_eq!(variant.rules, puzzle.rules);
+
+        // Any different clue makes a different puzzle
+        let mut changed = puzzle.clone();
+        changed.rules[0].value += 1;
+        assert_ne!(fingerprint(&changed), print);
+        assert_eq!(print.to_string().len(), 16);
+    }
+}
@@ -1,63 +1,66 @@
 use std::{
-    cell::Cell,
+    cell::{Cell, RefCell},
     collections::HashSet,
     fmt::{Display, Write},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use rand::{
-    fill, rng,
+    Rng, SeedableRng, rng,
     seq::{IndexedRandom, SliceRandom},
 };
-
-use crate::anchors::generate_anchors;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    anchors::{AnchorStrategy, JitteredLattice},
+    error::GenError,
+    fill::{fill_puzzle, refill},
+    fingerprint::fingerprint,
+    mask::Mask,
+    progress::{Progress, ProgressObserver},
+    puzzle::Puzzle,
+    report::{GenReport, GridOutcome, GridReport},
+    uniqueness::{add_clue, find_solutions},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct Component {
-    pub(crate) width: u8,
-    pub(crate) height: u8,
+pub struct Component {
+    pub width: u8,
+    pub height: u8,
 }
 
 impl Component {
-    pub(crate) const fn new(width: u8, height: u8) -> Self {
+    pub const fn new(width: u8, height: u8) -> Self {
         Self { width, height }
     }
 
-    fn get_cells(&self) -> Vec<Vec<GGCell>> {
-        // Cells contains one layer on each side for extra info
-        let mut cells =
-            vec![vec![GGCell::Uninitialized; self.width as usize + 2]; self.height as usize + 2];
-
-        // Set corners
-        cells[1][1] = GGCell::Corner(CornerDir::TopLeft);
-        cells[self.height as usize][1] = GGCell::Corner(CornerDir::BotLeft);
-        cells[1][self.width as usize] = GGCell::Corner(CornerDir::TopRight);
-        cells[self.height as usize][self.width as usize] = GGCell::Corner(CornerDir::BotRight);
-
-        // Set blocked cells
-        if self.width >= 5 {
-            for x in 3..self.width as usize - 1 {
-                cells[0][x] = GGCell::Blocked;
-                cells[self.height as usize + 1][x] = GGCell::Blocked;
-            }
+    /// Cell of the component at (x, y), with one layer around it on each side for extra info
+    fn cell_at(&self, x: u8, y: u8) -> GGCell {
+        let (w, h) = (self.width, self.height);
+
+        if (1..=w).contains(&x) && (1..=h).contains(&y) {
+            return match (x == w, y == h, x == 1, y == 1) {
+                (true, true, _, _) => GGCell::Corner(CornerDir::BotRight),
+                (true, _, _, true) => GGCell::Corner(CornerDir::TopRight),
+                (_, true, true, _) => GGCell::Corner(CornerDir::BotLeft),
+                (_, _, true, true) => GGCell::Corner(CornerDir::TopLeft),
+                _ => GGCell::Normal,
+            };
         }
 
-        if self.height >= 5 {
-            for y in 3..self.height as usize - 1 {
-                cells[y][0] = GGCell::Blocked;
-                cells[y][self.width as usize + 1] = GGCell::Blocked;
-            }
-        }
+        // Blocked cells stop neighbouring components from making the sides too long
+        let blocked_x = (y == 0 || y == h + 1) && w >= 5 && (3..w - 1).contains(&x);
+        let blocked_y = (x == 0 || x == w + 1) && h >= 5 && (3..h - 1).contains(&y);
 
-        // Set normal fillable cells
-        for y in 1..=self.height as usize {
-            for x in 1..=self.width as usize {
-                if cells[y][x] == GGCell::Uninitialized {
-                    cells[y][x] = GGCell::Normal
-                }
-            }
+        if blocked_x || blocked_y {
+            GGCell::Blocked
+        } else {
+            GGCell::Uninitialized
         }
-
-        cells
     }
 
     // Position of top left corner of component
@@ -74,14 +77,14 @@ impl Component {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct Anchor {
-    pub(crate) x: u8,
-    pub(crate) y: u8,
-    pub(crate) component: Component,
+pub struct Anchor {
+    pub x: u8,
+    pub y: u8,
+    pub component: Component,
 }
 
 impl Anchor {
-    pub(crate) const fn new(x: u8, y: u8, component: Component) -> Self {
+    pub const fn new(x: u8, y: u8, component: Component) -> Self {
         Self { x, y, component }
     }
 }
@@ -140,17 +143,65 @@ enum GGCell {
     Blocked,
 }
 
+impl GGCell {
+    fn is_fillable(self) -> bool {
+        matches!(self, Self::Normal | Self::Corner(_) | Self::FusedCorner)
+    }
+}
+
+/// Fixed-size set of cell indices
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BitSet(Vec<u64>);
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.0[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn set(&mut self, i: usize, value: bool) {
+        if value {
+            self.0[i / 64] |= 1 << (i % 64);
+        } else {
+            self.0[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn first(&self) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|&w| w != 0)
+            .map(|i| i * 64 + self.0[i].trailing_zeros() as usize)
+    }
+}
+
+/// Cells are stored flat, row by row, inside a border of blocked cells. The four neighbours of a cell are at fixed
+/// offsets from its index and never out of bounds
+#[derive(Clone)]
 struct Grid {
     width: u8,
     height: u8,
-    rows: Vec<Vec<GGCell>>,
+    /// Offset between vertically neighbouring cells
+    stride: usize,
+    cells: Vec<GGCell>,
+    /// Fillable cells, kept in step with `cells` by `set`
+    fillable: BitSet,
+    /// Cells the mask lets be fillable, never the border
+    allowed: BitSet,
 }
 
 impl Display for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in &self.rows {
-            for cell in row {
-                f.write_char(match cell {
+        for y in 0..self.height as usize {
+            for x in 0..self.width as usize {
+                f.write_char(match self.cells[self.index(x, y)] {
                     GGCell::Uninitialized => '_',
                     GGCell::Normal => 'O',
                     GGCell::Corner(_) => 'C',
@@ -166,59 +217,344 @@ impl Display for Grid {
     }
 }
 
-impl Grid {
-    fn generate(width: u8, height: u8) -> Self {
-        let rows = vec![vec![GGCell::Uninitialized; width as usize]; height as usize];
+// Anchor layouts can leave the grid disconnected or unfixable, in which case we start over
+const MAX_GENERATE_ATTEMPTS: usize = 100;
+
+// Each round of making a puzzle unique adds a clue or re-fills some cells, a 30x30 puzzle needs a few hundred
+const MAX_UNIQUENESS_ROUNDS: usize = 2000;
+
+/// Settings for a single grid generation
+#[derive(Clone, Debug)]
+pub struct GenConfig {
+    pub width: u8,
+    pub height: u8,
+    pub anchors: Arc<dyn AnchorStrategy>,
+    pub mask: Option<Mask>,
+    /// Makes generation repeatable, the same seed giving the same output for a given `GENERATOR_VERSION`
+    pub seed: Option<u64>,
+    /// Gives up with `GenError::TimedOut` after this long
+    pub time_limit: Option<Duration>,
+    /// Grids tried before giving up, per puzzle asked for. A grid that can't be filled, or whose puzzle is a duplicate
+    /// in a batch, counts as tried
+    pub max_attempts: usize,
+    /// Gives up with `GenError::Cancelled` once cancelled
+    pub cancel: Option<CancelToken>,
+    pub progress: Option<Arc<dyn ProgressObserver>>,
+}
 
-        let mut grid = Self {
+impl GenConfig {
+    pub fn new(width: u8, height: u8) -> Self {
+        Self {
             width,
             height,
-            rows,
-        };
+            anchors: Arc::new(JitteredLattice { jitter: 1 }),
+            mask: None,
+            seed: None,
+            time_limit: None,
+            max_attempts: MAX_GENERATE_ATTEMPTS,
+            cancel: None,
+            progress: None,
+        }
+    }
+
+    pub fn with_anchors(mut self, strategy: impl AnchorStrategy + 'static) -> Self {
+        self.anchors = Arc::new(strategy);
+        self
+    }
 
-        if width < 5 || height < 5 || width > 30 || height > 30 {
-            panic!("{}x{} size is unsupported", width, height)
+    /// Restricts fillable cells to those allowed by `mask`, which must be the same size as the grid
+    pub fn with_mask(mut self, mask: Mask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Limits how long a single call may take. Checked between attempts and while growing and fixing up a grid, so
+    /// it can run over by about one fill
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    pub fn with_max_attempts(mut self, attempts: usize) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Lets another thread stop generation, checked as often as the time limit
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Reports each stage of generation to `observer`, which can be a closure taking a `Progress`
+    pub fn with_progress(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.progress = Some(Arc::new(observer));
+        self
+    }
+
+    /// Random source for one generation run, from the seed if there is one
+    pub(crate) fn rng(&self) -> ChaCha8Rng {
+        match self.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_rng(&mut rng()),
         }
+    }
+}
 
-        // Create anchors
-        let count = (
-            if width >= 8 { 3 } else { 2 },
-            if height >= 8 { 3 } else { 2 },
-        );
-        let comp = (2.max(width / 4), 2.max(height / 4));
+/// Stops a generation running on another thread. Clones share the same flag
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
 
-        let anchors = generate_anchors((width, height), comp, count);
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// One call to generation: its time limit and cancel token, who to tell about its progress, and what it's tried so far
+struct Run {
+    deadline: Option<Instant>,
+    cancel: Option<CancelToken>,
+    observer: Option<Arc<dyn ProgressObserver>>,
+    /// Grids the whole call may try. Failed fills and duplicate puzzles use it up too, as each needs a new grid
+    max_grids: usize,
+    grid_attempts: Cell<usize>,
+    fill_attempts: Cell<usize>,
+    started: Instant,
+    report: RefCell<GenReport>,
+}
+
+impl Run {
+    fn start(config: &GenConfig) -> Self {
+        Self {
+            deadline: config.time_limit.map(|limit| Instant::now() + limit),
+            cancel: config.cancel.clone(),
+            observer: config.progress.clone(),
+            max_grids: config.max_attempts,
+            grid_attempts: Cell::new(0),
+            fill_attempts: Cell::new(0),
+            started: Instant::now(),
+            report: RefCell::default(),
+        }
+    }
+
+    /// No limits and nobody to report to
+    fn unlimited() -> Self {
+        Self {
+            deadline: None,
+            cancel: None,
+            observer: None,
+            max_grids: usize::MAX,
+            grid_attempts: Cell::new(0),
+            fill_attempts: Cell::new(0),
+            started: Instant::now(),
+            report: RefCell::default(),
+        }
+    }
+
+    fn notify(&self, progress: Progress) {
+        tracing::debug!(?progress);
+
+        if let Some(observer) = &self.observer {
+            observer.on_progress(progress);
+        }
+    }
+
+    /// Counts a new grid, returning its attempt number
+    fn next_grid(&self) -> usize {
+        self.grid_attempts.set(self.grid_attempts.get() + 1);
+        self.grid_attempts.get()
+    }
+
+    /// Updates the report for the grid being generated
+    fn update_grid(&self, f: impl FnOnce(&mut GridReport)) {
+        if let Some(grid) = self.report.borrow_mut().grids.last_mut() {
+            f(grid);
+        }
+    }
+
+    fn finish(self) -> GenReport {
+        let mut report = self.report.into_inner();
+        report.elapsed = self.started.elapsed();
+        report
+    }
+
+    fn next_fill(&self) -> usize {
+        self.fill_attempts.set(self.fill_attempts.get() + 1);
+        self.fill_attempts.get()
+    }
+
+    fn out_of_grids(&self) -> bool {
+        self.grid_attempts.get() >= self.max_grids
+    }
+
+    /// Why the grids ran out: the mask if it's set and every grid tried was rejected, otherwise the budget. Grids that
+    /// were accepted but failed to fill or made a duplicate puzzle aren't the mask's fault
+    fn exhausted(&self, config: &GenConfig) -> GenError {
+        let grids = &self.report.borrow().grids;
+
+        if config.mask.is_some()
+            && !grids.is_empty()
+            && grids.iter().all(|g| g.outcome != GridOutcome::Accepted)
+        {
+            GenError::MaskUnusable
+        } else {
+            GenError::AttemptsExhausted
+        }
+    }
+
+    fn check(&self) -> Result<(), GenError> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Err(GenError::Cancelled)
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Err(GenError::TimedOut)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Bumped whenever generation changes so that a seed gives a different puzzle than before, including changes to how
+/// `rand` draws numbers, or to how the daily puzzle is picked. Any bump changes the daily puzzle of every date, past
+/// ones included, so anything stored by seed has to record it
+pub const GENERATOR_VERSION: u32 = 5;
+
+impl Grid {
+    fn generate(config: &GenConfig) -> Result<Self, GenError> {
+        Self::generate_with(config, &mut config.rng(), &Run::start(config))
+    }
+
+    fn generate_with(config: &GenConfig, rng: &mut impl Rng, run: &Run) -> Result<Self, GenError> {
+        let (width, height) = (config.width, config.height);
+
+        // Below 8 cells a side there's no room to grow runs around the anchors, so every attempt would fail
+        if width < 8 || height < 8 || width > 30 || height > 30 {
+            return Err(GenError::UnsupportedSize { width, height });
+        }
+
+        if let Some(mask) = &config.mask {
+            if (mask.width(), mask.height()) != (width, height) {
+                return Err(GenError::MaskSizeMismatch {
+                    expected: (width, height),
+                    found: (mask.width(), mask.height()),
+                });
+            }
+            if !mask.has_room() {
+                return Err(GenError::MaskUnusable);
+            }
+        }
+
+        while !run.out_of_grids() {
+            run.check()?;
+
+            if let Some(grid) = Self::try_generate(config, rng, run)? {
+                return Ok(grid);
+            }
+        }
+
+        Err(run.exhausted(config))
+    }
+
+    fn try_generate(
+        config: &GenConfig,
+        rng: &mut impl Rng,
+        run: &Run,
+    ) -> Result<Option<Self>, GenError> {
+        let (mut grid, mut active_corners) = Self::place_anchors(config, rng);
+        let attempt = run.next_grid();
+        let _span = tracing::debug_span!("grid", attempt).entered();
+
+        // Each anchor that fit starts off with its 4 corners
+        let anchors = active_corners.len() / 4;
+        run.report.borrow_mut().grids.push(GridReport {
+            anchors,
+            ..Default::default()
+        });
+        run.notify(Progress::AnchorsPlaced { attempt, anchors });
+
+        grid.grow(&mut active_corners, rng, run)?;
+
+        if !grid.check_is_connected() {
+            run.update_grid(|g| g.outcome = GridOutcome::Disconnected);
+            tracing::debug!("grid rejected, not connected");
+            return Ok(None);
+        }
+
+        grid.fix_up(run)?;
+
+        if !grid.has_valid_cages() {
+            run.update_grid(|g| g.outcome = GridOutcome::InvalidRuns);
+            tracing::debug!("grid rejected, runs out of range");
+            return Ok(None);
+        }
+
+        run.update_grid(|g| g.outcome = GridOutcome::Accepted);
+
+        Ok(Some(grid))
+    }
+
+    /// Empty grid with the anchors placed, and the corners to grow from
+    fn place_anchors(config: &GenConfig, rng: &mut impl Rng) -> (Self, Vec<Corner>) {
+        let (width, height) = (config.width, config.height);
+        let mut grid = Self::new(width, height, config.mask.as_ref());
+
+        // Create anchors
+        let anchors = config.anchors.place_anchors(width, height, rng);
 
         let mut active_corners: Vec<Corner> = Vec::new();
 
-        // Initialize anchors
+        // Initialize anchors, skipping any that don't fit
         for anchor in anchors {
-            active_corners.append(
-                &mut Corner::generate_corners(anchor.x, anchor.y, anchor.component).to_vec(),
-            );
-            grid.try_place_component(anchor.x, anchor.y, anchor.component);
+            if grid.try_place_component(anchor.x, anchor.y, anchor.component) {
+                active_corners.append(
+                    &mut Corner::generate_corners(anchor.x, anchor.y, anchor.component).to_vec(),
+                );
+            }
         }
 
-        let rng = &mut rng();
+        (grid, active_corners)
+    }
 
+    /// Keeps adding components at the active corners until none of them have room left, then blocks the rest
+    fn grow(
+        &mut self,
+        active_corners: &mut Vec<Corner>,
+        rng: &mut impl Rng,
+        run: &Run,
+    ) -> Result<(), GenError> {
+        let (width, height) = (self.width, self.height);
         let mut wasted_iters = 0;
-        let mut total_comps: Vec<(u8, u8, u8, u8)> = vec![];
+        let mut components = 0;
+        let mut iterations = 0;
+        let mut wasted = 0;
 
         loop {
+            run.check()?;
+
             // Check for dead corners
-            active_corners = active_corners
-                .into_iter()
-                .filter(|c| !grid.corner_is_dead(c))
-                .collect();
+            active_corners.retain(|c| !self.corner_is_dead(c));
 
             if active_corners.is_empty() {
                 break;
-            } else {
-                //println!("{}", active_corners.len())
             }
 
+            iterations += 1;
+
             // Select a random corner to try to build from
-            let rcorn = active_corners.choose(rng).unwrap_or(&active_corners[0]);
+            let rcorn = *active_corners.choose(rng).unwrap_or(&active_corners[0]);
 
             // Try to place new component
             let max_w = (width / 4).min(2);
@@ -244,14 +580,14 @@ impl Grid {
 
                     let (ux, uy) = (pos.0 as u8, pos.1 as u8);
 
-                    if grid.try_place_component(ux, uy, component) {
+                    if self.try_place_component(ux, uy, component) {
                         // Update active corners
                         active_corners.append(
                             &mut Corner::generate_corners(ux, uy, component)
                                 .into_iter()
                                 .collect(),
                         );
-                        total_comps.push((ux, uy, *w, *h));
+                        components += 1;
                         wasted_iters = -1;
                         break 'outer;
                     }
@@ -260,33 +596,97 @@ impl Grid {
 
             wasted_iters += 1;
 
+            if wasted_iters > 0 {
+                wasted += 1;
+            }
+
             if wasted_iters > 100 {
-                println!("{:?}", active_corners);
+                tracing::debug!(
+                    corners = active_corners.len(),
+                    "growth stalled, giving up on the remaining corners"
+                );
                 break;
             }
         }
 
-        if !grid.check_is_connected() {
-            panic!("Grid not connected: {}", grid);
-        }
+        self.block_remaining();
+
+        run.update_grid(|g| {
+            g.components = components;
+            g.growth_iterations = iterations;
+            g.wasted_iterations = wasted;
+            g.corners_remaining = active_corners.len();
+        });
+        run.notify(Progress::GrowthFinished {
+            attempt: run.grid_attempts.get(),
+            components,
+        });
+
+        Ok(())
+    }
 
-        grid.fix_up();
+    /// Blocks the cells the growth loop didn't reach
+    fn block_remaining(&mut self) {
+        for cell in &mut self.cells {
+            if *cell == GGCell::Uninitialized {
+                *cell = GGCell::Blocked;
+            }
+        }
+    }
 
-        if grid
-            .get_cages()
+    fn has_valid_cages(&self) -> bool {
+        self.get_cages()
             .into_iter()
             .filter(|c| c.3 < 2 || c.3 > 9)
             .count()
-            != 0
-        {
-            panic!("Unable to fix grid")
+            == 0
+    }
+
+    fn new(width: u8, height: u8, mask: Option<&Mask>) -> Self {
+        let stride = width as usize + 2;
+        let len = stride * (height as usize + 2);
+
+        let mut grid = Self {
+            width,
+            height,
+            stride,
+            cells: vec![GGCell::Blocked; len],
+            fillable: BitSet::new(len),
+            allowed: BitSet::new(len),
+        };
+
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let i = grid.index(x, y);
+                grid.cells[i] = GGCell::Uninitialized;
+                grid.allowed.set(i, mask.is_none_or(|m| m.is_allowed(x, y)));
+            }
         }
 
         grid
     }
 
+    fn index(&self, x: usize, y: usize) -> usize {
+        (y + 1) * self.stride + x + 1
+    }
+
+    fn set(&mut self, i: usize, cell: GGCell) {
+        self.cells[i] = cell;
+        self.fillable.set(i, cell.is_fillable());
+    }
+
+    /// Left, right, up and down
+    fn neighbours(&self, i: usize) -> [usize; 4] {
+        [i - 1, i + 1, i - self.stride, i + self.stride]
+    }
+
     fn try_place_component(&mut self, cx: u8, cy: u8, component: Component) -> bool {
-        if cx + component.width > self.width || cy + component.height > self.height {
+        // Anchors come from the caller and can be any size or position, widen so they can't overflow
+        if component.width < 2
+            || component.height < 2
+            || cx as usize + component.width as usize > self.width as usize
+            || cy as usize + component.height as usize > self.height as usize
+        {
             return false;
         }
         // Lower and upper bounds of index into component grid cells (e.g. if the component is being placed at x = 0, we don't
@@ -306,15 +706,18 @@ impl Grid {
             component.height + 1
         };
 
-        let cells = component.get_cells();
-
         // Check for conflicts, don't update cells yet
         for y in lower_y..=upper_y {
             for x in lower_x..=upper_x {
-                let comp_cell = cells[y as usize][x as usize];
-                let (ix, iy) = ((cx + x - 1) as usize, (cy + y - 1) as usize);
+                let comp_cell = component.cell_at(x, y);
+                let i = self.index((cx + x - 1) as usize, (cy + y - 1) as usize);
+
+                // Fillable cells can only go where the mask allows them
+                if comp_cell.is_fillable() && !self.allowed.contains(i) {
+                    return false;
+                }
 
-                match &self.rows[iy][ix] {
+                match &self.cells[i] {
                     GGCell::Normal => {
                         if comp_cell != GGCell::Uninitialized {
                             return false;
@@ -339,15 +742,15 @@ impl Grid {
 
         for y in lower_y..=upper_y {
             for x in lower_x..=upper_x {
-                let comp_cell = cells[y as usize][x as usize];
-                let (ix, iy) = ((cx + x - 1) as usize, (cy + y - 1) as usize);
+                let comp_cell = component.cell_at(x, y);
+                let i = self.index((cx + x - 1) as usize, (cy + y - 1) as usize);
 
-                match &self.rows[iy][ix] {
-                    GGCell::Uninitialized => self.rows[iy][ix] = comp_cell,
+                match &self.cells[i] {
+                    GGCell::Uninitialized => self.set(i, comp_cell),
                     GGCell::Corner(_) => {
                         // We already checked that corners match
                         if let GGCell::Corner(_) = comp_cell {
-                            self.rows[iy][ix] = GGCell::FusedCorner
+                            self.set(i, GGCell::FusedCorner)
                         }
                     }
                     _ => {} // Any other cells should not be overwritten,
@@ -368,8 +771,8 @@ impl Grid {
             return true;
         }
 
-        let empty_cells: [(i8, i8); 2];
-        let corner_cells: [(i8, i8); 3];
+        let empty_cells: [(isize, isize); 2];
+        let corner_cells: [(isize, isize); 3];
 
         match corner.dir {
             CornerDir::TopLeft => {
@@ -390,18 +793,21 @@ impl Grid {
             }
         }
 
-        let (x, y) = (corner.x as i8, corner.y as i8);
+        let i = self.index(corner.x as usize, corner.y as usize);
+        let cell_at = |(dx, dy): (isize, isize)| {
+            self.cells[i.wrapping_add_signed(dx + dy * self.stride as isize)]
+        };
 
-        for (dx, dy) in empty_cells {
-            let cell = self.rows[(y + dy) as usize][(x + dx) as usize];
+        for offset in empty_cells {
+            let cell = cell_at(offset);
 
             if cell != GGCell::Uninitialized && cell != GGCell::Blocked {
                 return true;
             }
         }
 
-        for (dx, dy) in corner_cells {
-            let cell = self.rows[(y + dy) as usize][(x + dx) as usize];
+        for offset in corner_cells {
+            let cell = cell_at(offset);
 
             if cell != GGCell::Uninitialized
                 && cell != GGCell::Corner(corner.dir.get_opposite_dir())
@@ -413,102 +819,35 @@ impl Grid {
         false
     }
 
-    fn get_cell_fillable(&self, x: usize, y: usize) -> bool {
-        if x as u8 >= self.width || y as u8 >= self.height {
-            false
-        } else {
-            matches!(
-                self.rows[y][x],
-                GGCell::Normal | GGCell::Corner(_) | GGCell::FusedCorner
-            )
-        }
-    }
-
-    fn get_fillable_cell_count(&self) -> u32 {
-        let mut count = 0;
+    fn check_is_connected(&self) -> bool {
+        // Start from the first fillable cell, the top-left one may be blocked
+        let Some(start) = self.fillable.first() else {
+            return false;
+        };
 
-        for y in 0..self.height as usize {
-            for x in 0..self.width as usize {
-                if self.get_cell_fillable(x, y) {
-                    count += 1
+        let mut seen = BitSet::new(self.cells.len());
+        let mut stack = vec![start];
+        let mut reached = 1;
+        seen.set(start, true);
+
+        while let Some(i) = stack.pop() {
+            for next in self.neighbours(i) {
+                if self.fillable.contains(next) && !seen.contains(next) {
+                    seen.set(next, true);
+                    stack.push(next);
+                    reached += 1;
                 }
             }
         }
 
-        count
+        reached == self.fillable.count()
     }
 
-    fn check_is_connected(&self) -> bool {
-        let fillable_cells = self.get_fillable_cell_count() as usize;
-
-        let mut cell_queue: Vec<(usize, usize)> = Vec::new();
-        let mut cell_set: HashSet<(usize, usize)> = HashSet::new();
-
-        let mut curr_cell = (0, 0);
-        cell_queue.push(curr_cell);
-        cell_set.insert(curr_cell);
-
-        loop {
-            let last_curr_cell = curr_cell; // Save to check for change after
-
-            // Try go left
-            if curr_cell.0 > 0
-                && !cell_set.contains(&(curr_cell.0 - 1, curr_cell.1))
-                && self.get_cell_fillable(curr_cell.0 - 1, curr_cell.1)
-            {
-                let cell = (curr_cell.0 - 1, curr_cell.1);
-                cell_set.insert(cell);
-                cell_queue.push(cell);
-                curr_cell = cell;
-            }
-            // Try go right
-            else if curr_cell.0 < self.width as usize - 1
-                && !cell_set.contains(&(curr_cell.0 + 1, curr_cell.1))
-                && self.get_cell_fillable(curr_cell.0 + 1, curr_cell.1)
-            {
-                let cell = (curr_cell.0 + 1, curr_cell.1);
-                cell_set.insert(cell);
-                cell_queue.push(cell);
-                curr_cell = cell;
-            }
-            // Try go up
-            else if curr_cell.1 > 0
-                && !cell_set.contains(&(curr_cell.0, curr_cell.1 - 1))
-                && self.get_cell_fillable(curr_cell.0, curr_cell.1 - 1)
-            {
-                let cell = (curr_cell.0, curr_cell.1 - 1);
-                cell_set.insert(cell);
-                cell_queue.push(cell);
-                curr_cell = cell;
-            }
-            // Try go down
-            else if curr_cell.1 < self.height as usize - 1
-                && !cell_set.contains(&(curr_cell.0, curr_cell.1 + 1))
-                && self.get_cell_fillable(curr_cell.0, curr_cell.1 + 1)
-            {
-                let cell = (curr_cell.0, curr_cell.1 + 1);
-                cell_set.insert(cell);
-                cell_queue.push(cell);
-                curr_cell = cell;
-            }
-
-            if last_curr_cell == curr_cell {
-                // Reached a dead end (didn't update cell), try to backtrack
-                if cell_queue.is_empty() {
-                    // No cells to backtrack to
-                    return false;
-                } else {
-                    curr_cell = cell_queue
-                        .pop()
-                        .expect("Unable to take element from non-empty list");
-                }
-            }
-
-            // Check if we've reached the last cell
-            if cell_set.len() == fillable_cells {
-                return true;
-            }
-        }
+    /// Number of fillable cells from `i` onwards, moving `step` at a time
+    fn run_from(&self, i: usize, step: usize) -> usize {
+        (0..)
+            .take_while(|k| self.fillable.contains(i + k * step))
+            .count()
     }
 
     fn get_cages(
@@ -519,48 +858,31 @@ impl Grid {
         bool,  /*is_vertical*/
         usize, /*len*/
     )> {
+        let (w, h) = (self.width as usize, self.height as usize);
         let mut res = Vec::new();
 
         // Check rows
-        for y in 0..self.height {
-            let mut x = 0u8;
-            loop {
-                if self.get_cell_fillable(x as usize, y as usize) {
-                    // Found a new block, process it
-                    let mut entry = (x, y, false, 1);
-                    x += 1;
-
-                    while self.get_cell_fillable(x as usize, y as usize) {
-                        entry.3 += 1;
-                        x += 1;
+        for y in 0..h {
+            let mut x = 0;
+            while x < w {
+                match self.run_from(self.index(x, y), 1) {
+                    0 => x += 1,
+                    len => {
+                        res.push((x as u8, y as u8, false, len));
+                        x += len;
                     }
-
-                    res.push(entry);
-                } else if x < self.width - 1 {
-                    x += 1;
-                } else {
-                    break;
                 }
             }
         }
-        for x in 0..self.width {
-            let mut y = 0u8;
-            loop {
-                if self.get_cell_fillable(x as usize, y as usize) {
-                    // Found a new block, process it
-                    let mut entry = (x, y, true, 1);
-                    y += 1;
-
-                    while self.get_cell_fillable(x as usize, y as usize) {
-                        entry.3 += 1;
-                        y += 1;
+        for x in 0..w {
+            let mut y = 0;
+            while y < h {
+                match self.run_from(self.index(x, y), self.stride) {
+                    0 => y += 1,
+                    len => {
+                        res.push((x as u8, y as u8, true, len));
+                        y += len;
                     }
-
-                    res.push(entry);
-                } else if y < self.height - 1 {
-                    y += 1;
-                } else {
-                    break;
                 }
             }
         }
@@ -568,128 +890,182 @@ impl Grid {
         res
     }
 
-    fn fix_up(&mut self) {
-        // Get rid of any cages that are too long
-        let mut cages = self.get_cages();
-        let mut i = 0;
+    /// Fillable cells whose removal would split the fillable cells into separate parts, found with Tarjan's algorithm
+    fn cut_cells(&self) -> BitSet {
+        let len = self.cells.len();
+        let mut cut = BitSet::new(len);
 
-        // Fix any >9 length cages
-        loop {
-            let (x, y, is_vertical, len) = cages[i];
-            if len > 9 {
-                let midpoint: u8 = len as u8 / 2;
+        // Discovery order of each cell, 0 for unvisited, and the earliest cell reachable through one back edge
+        let mut order = vec![0usize; len];
+        let mut low = vec![0usize; len];
+        let mut counter = 0;
 
-                for j in midpoint..len as u8 {
-                    let (nx, ny) = if is_vertical { (x, y + j) } else { (x + j, y) };
-                    let old_state = self.rows[ny as usize][nx as usize];
+        for root in 0..len {
+            if order[root] != 0 || !self.fillable.contains(root) {
+                continue;
+            }
+
+            counter += 1;
+            order[root] = counter;
+            low[root] = counter;
+            let mut root_children = 0;
+
+            // Cell, its parent, and the next direction to look in
+            let mut stack = vec![(root, root, 0)];
+
+            while let Some(&mut (cell, parent, ref mut dir)) = stack.last_mut() {
+                if *dir < 4 {
+                    let next = self.neighbours(cell)[*dir];
+                    *dir += 1;
+
+                    if !self.fillable.contains(next) {
+                        continue;
+                    }
 
-                    self.rows[ny as usize][nx as usize] = GGCell::Blocked;
-                    if !self.check_is_connected() {
-                        self.rows[ny as usize][nx as usize] = old_state;
-                    } else {
-                        break;
+                    if order[next] == 0 {
+                        counter += 1;
+                        order[next] = counter;
+                        low[next] = counter;
+                        stack.push((next, cell, 0));
+                    } else if next != parent {
+                        low[cell] = low[cell].min(order[next]);
                     }
+
+                    continue;
                 }
 
-                for j in (0..midpoint as u8).rev() {
-                    let (nx, ny) = if is_vertical { (x, y + j) } else { (x + j, y) };
-                    let old_state = self.rows[ny as usize][nx as usize];
+                stack.pop();
 
-                    self.rows[ny as usize][nx as usize] = GGCell::Blocked;
-                    if !self.check_is_connected() {
-                        self.rows[ny as usize][nx as usize] = old_state;
-                    } else {
-                        break;
-                    }
+                if parent == cell {
+                    continue;
                 }
-            }
 
-            let new_cages = self.get_cages();
+                low[parent] = low[parent].min(low[cell]);
 
-            if new_cages.len() != cages.len() {
-                cages = new_cages;
-                i = 0
-            } else {
-                i += 1;
-                if i >= cages.len() {
-                    break;
+                if parent == root {
+                    root_children += 1;
+                } else if low[cell] >= order[parent] {
+                    cut.set(parent, true);
                 }
             }
+
+            cut.set(root, root_children > 1);
         }
 
-        cages = self.get_cages();
-        i = 0;
+        cut
+    }
 
-        // Fix any <2 length cages
+    /// Lengths of the horizontal and vertical runs through a fillable cell
+    fn run_lengths_at(&self, i: usize) -> (usize, usize) {
+        let count = |step: isize| {
+            (1..)
+                .take_while(|k| self.fillable.contains(i.wrapping_add_signed(step * k)))
+                .count()
+        };
+        let stride = self.stride as isize;
+
+        (count(-1) + 1 + count(1), count(-stride) + 1 + count(stride))
+    }
+
+    /// Makes a blocked cell fillable, unless that creates a run longer than 9. The grid must be connected, which
+    /// stays true as the cell always neighbours a fillable one
+    fn try_unblock(&mut self, i: usize) -> bool {
+        let old_state = self.cells[i];
+        self.set(i, GGCell::Normal);
+
+        let (across, down) = self.run_lengths_at(i);
+        if across > 9 || down > 9 {
+            self.set(i, old_state);
+            return false;
+        }
+
+        true
+    }
+
+    // Only called on connected grids, and keeps them connected
+    fn fix_up(&mut self, run: &Run) -> Result<(), GenError> {
+        // Get rid of any cages that are too long
+        let mut cages = self.get_cages();
+        let mut cut = self.cut_cells();
+        let mut i = 0;
+        let mut blocked = 0;
+
+        // Fix any >9 length cages
         loop {
-            let (x, y, is_vertical, len) = cages[i];
-            if len < 2 {
-                if is_vertical {
-                    if y != 0 {
-                        let (ux, uy) = (x as usize, y as usize - 1);
-                        let old_state = self.rows[uy][ux];
+            run.check()?;
 
-                        self.rows[uy][ux] = GGCell::Normal;
+            let (x, y, is_vertical, len) = cages[i];
+            let start = self.index(x as usize, y as usize);
+            let step = if is_vertical { self.stride } else { 1 };
+            let mut changed = false;
 
-                        if !self.check_is_connected()
-                            || self.get_cages().iter().filter(|c| c.3 > 9).count() != 0
-                        {
-                            self.rows[uy][ux] = old_state;
-                        } else {
-                            cages = self.get_cages();
-                            i = 0;
-                            continue;
+            if len > 9 {
+                let midpoint = len / 2;
+
+                // Block the first cell from the middle outwards, in each half, that doesn't split the grid
+                for range in [
+                    (midpoint..len).collect::<Vec<_>>(),
+                    (0..midpoint).rev().collect(),
+                ] {
+                    for j in range {
+                        let cell = start + j * step;
+
+                        if !cut.contains(cell) {
+                            self.set(cell, GGCell::Blocked);
+                            cut = self.cut_cells();
+                            changed = true;
+                            blocked += 1;
+                            break;
                         }
                     }
-                    if y < self.height - 1 {
-                        let (ux, uy) = (x as usize, y as usize + 1);
-                        let old_state = self.rows[uy][ux];
+                }
+            }
 
-                        self.rows[uy][ux] = GGCell::Normal;
+            if changed {
+                let new_cages = self.get_cages();
+                if new_cages.len() != cages.len() {
+                    cages = new_cages;
+                    i = 0;
+                    continue;
+                }
+            }
 
-                        if !self.check_is_connected()
-                            || self.get_cages().iter().filter(|c| c.3 > 9).count() != 0
-                        {
-                            self.rows[uy][ux] = old_state;
-                        } else {
-                            cages = self.get_cages();
-                            i = 0;
-                            continue;
-                        }
-                    }
-                } else {
-                    if x != 0 {
-                        let (ux, uy) = (x as usize - 1, y as usize);
-                        let old_state = self.rows[uy][ux];
+            i += 1;
+            if i >= cages.len() {
+                break;
+            }
+        }
 
-                        self.rows[uy][ux] = GGCell::Normal;
+        run.update_grid(|g| g.runs_split = blocked);
+        run.notify(Progress::FixUpPass {
+            attempt: run.grid_attempts.get(),
+            pass: 1,
+            changed: blocked,
+        });
 
-                        if !self.check_is_connected()
-                            || self.get_cages().iter().filter(|c| c.3 > 9).count() != 0
-                        {
-                            self.rows[uy][ux] = old_state;
-                        } else {
-                            cages = self.get_cages();
-                            i = 0;
-                            continue;
-                        }
-                    }
-                    if x < self.width - 1 {
-                        let (ux, uy) = (x as usize + 1, y as usize);
-                        let old_state = self.rows[uy][ux];
+        cages = self.get_cages();
+        i = 0;
+        let mut unblocked = 0;
 
-                        self.rows[uy][ux] = GGCell::Normal;
+        // Fix any <2 length cages
+        loop {
+            run.check()?;
 
-                        if !self.check_is_connected()
-                            || self.get_cages().iter().filter(|c| c.3 > 9).count() != 0
-                        {
-                            self.rows[uy][ux] = old_state;
-                        } else {
-                            cages = self.get_cages();
-                            i = 0;
-                            continue;
-                        }
-                    }
+            let (x, y, is_vertical, len) = cages[i];
+            if len < 2 {
+                let start = self.index(x as usize, y as usize);
+                let step = if is_vertical { self.stride } else { 1 };
+
+                // Grow the cage at either end, the border is never allowed
+                let grown = [start - step, start + step]
+                    .into_iter()
+                    .any(|end| self.allowed.contains(end) && self.try_unblock(end));
+
+                if grown {
+                    cages = self.get_cages();
+                    i = 0;
+                    unblocked += 1;
+                    continue;
                 }
             }
 
@@ -698,14 +1074,22 @@ impl Grid {
                 break;
             }
         }
+
+        run.update_grid(|g| g.runs_grown = unblocked);
+        run.notify(Progress::FixUpPass {
+            attempt: run.grid_attempts.get(),
+            pass: 2,
+            changed: unblocked,
+        });
+
+        Ok(())
     }
 
-    fn to_bool_vec(self) -> Vec<Vec<bool>> {
-        self.rows
-            .into_iter()
-            .map(|r| {
-                r.into_iter()
-                    .map(|c| matches!(c, GGCell::Normal | GGCell::Corner(_) | GGCell::FusedCorner))
+    fn to_bool_vec(&self) -> Vec<Vec<bool>> {
+        (0..self.height as usize)
+            .map(|y| {
+                (0..self.width as usize)
+                    .map(|x| self.fillable.contains(self.index(x, y)))
                     .collect()
             })
             .collect()
@@ -713,20 +1097,248 @@ impl Grid {
 }
 
 pub fn get_grid(width: u8, height: u8) -> Vec<Vec<bool>> {
-    //let grid = Grid::generate(8, 8);
-    let grid = Grid::generate(width, height);
-
-    println!("{}", grid);
+    let grid = Grid::generate(&GenConfig::new(width, height)).unwrap_or_else(|e| panic!("{}", e));
 
     grid.to_bool_vec()
 }
 
+/// Span covering one generation call
+fn call_span(name: &'static str, config: &GenConfig) -> tracing::Span {
+    tracing::debug_span!(
+        "generate",
+        call = name,
+        width = config.width,
+        height = config.height,
+        seed = config.seed
+    )
+}
+
+/// Generates a grid with the given settings, `true` marks a fillable cell
+pub fn generate_grid(config: &GenConfig) -> Result<Vec<Vec<bool>>, GenError> {
+    generate_grid_report(config).0
+}
+
+/// `generate_grid`, along with what it tried on the way
+pub fn generate_grid_report(config: &GenConfig) -> (Result<Vec<Vec<bool>>, GenError>, GenReport) {
+    let _span = call_span("grid", config).entered();
+    let run = Run::start(config);
+    let result = Grid::generate_with(config, &mut config.rng(), &run).map(|g| g.to_bool_vec());
+
+    (result, run.finish())
+}
+
+/// Generates a grid and fills it in, giving a puzzle with its rule values and solution. The solution is the only one
+pub fn generate_puzzle(config: &GenConfig) -> Result<Puzzle, GenError> {
+    generate_puzzle_report(config).0
+}
+
+/// `generate_puzzle`, along with what it tried on the way
+pub fn generate_puzzle_report(config: &GenConfig) -> (Result<Puzzle, GenError>, GenReport) {
+    let _span = call_span("puzzle", config).entered();
+    let run = Run::start(config);
+    let result = generate_puzzle_with(config, &mut config.rng(), &run);
+
+    (result, run.finish())
+}
+
+fn generate_puzzle_with(
+    config: &GenConfig,
+    rng: &mut impl Rng,
+    run: &Run,
+) -> Result<Puzzle, GenError> {
+    // Runs out once `generate_with` does
+    loop {
+        run.check()?;
+
+        let shape = Grid::generate_with(config, rng, run)?.to_bool_vec();
+        let puzzle = fill_puzzle(&Puzzle::from_shape(&shape), rng);
+
+        {
+            let mut report = run.report.borrow_mut();
+            report.fill_attempts += 1;
+            if puzzle.is_none() {
+                report.failed_fills += 1;
+            }
+        }
+        run.notify(Progress::FillAttempt {
+            attempt: run.next_fill(),
+            filled: puzzle.is_some(),
+        });
+
+        if let Some(puzzle) = puzzle
+            && let Some(puzzle) = make_unique(puzzle, rng, run)?
+        {
+            return Ok(puzzle);
+        }
+    }
+}
+
+/// Keeps looking for a second solution to the filled puzzle and taking it away, by turning a cell where the two
+/// differ into a clue or re-filling those cells when none of them can be one. `None` if that doesn't get to a single
+/// solution
+fn make_unique(
+    mut puzzle: Puzzle,
+    rng: &mut impl Rng,
+    run: &Run,
+) -> Result<Option<Puzzle>, GenError> {
+    for _ in 0..MAX_UNIQUENESS_ROUNDS {
+        run.check()?;
+
+        let solutions = find_solutions(&puzzle, 2);
+        let unique = solutions.as_ref().is_some_and(|s| s.len() == 1);
+
+        let checked = {
+            let mut report = run.report.borrow_mut();
+            report.uniqueness_checks += 1;
+            report.uniqueness_checks
+        };
+        run.notify(Progress::UniquenessCheck { checked, unique });
+
+        if unique {
+            return Ok(Some(puzzle));
+        }
+
+        let (Some(known), Some(solutions)) = (&puzzle.solution, solutions) else {
+            break;
+        };
+        let Some(other) = solutions.iter().find(|s| *s != known) else {
+            break;
+        };
+
+        let mut differing: Vec<(u8, u8)> = puzzle
+            .fillable_cells()
+            .filter(|&(x, y)| known[y as usize][x as usize] != other[y as usize][x as usize])
+            .collect();
+        differing.shuffle(rng);
+
+        if let Some(clued) = differing.iter().find_map(|&(x, y)| add_clue(&puzzle, x, y)) {
+            run.report.borrow_mut().clues_added += 1;
+            puzzle = clued;
+        } else if let Some(refilled) = refill(&puzzle, &differing, rng) {
+            puzzle = refilled;
+        }
+    }
+
+    run.report.borrow_mut().ambiguous += 1;
+    tracing::debug!("puzzle rejected, more than one solution");
+
+    Ok(None)
+}
+
+/// Generates `count` puzzles with the same settings, none of them a rotation or reflection of another
+pub fn generate_batch(config: &GenConfig, count: usize) -> Result<Vec<Puzzle>, GenError> {
+    generate_batch_report(config, count).0
+}
+
+/// `generate_batch`, along with what it tried on the way
+pub fn generate_batch_report(
+    config: &GenConfig,
+    count: usize,
+) -> (Result<Vec<Puzzle>, GenError>, GenReport) {
+    let _span = call_span("batch", config).entered();
+    let run = Run {
+        // Each puzzle gets as many grids as a call for a single one would
+        max_grids: config.max_attempts.saturating_mul(count),
+        ..Run::start(config)
+    };
+    let result = generate_batch_with(config, count, &run);
+
+    (result, run.finish())
+}
+
+fn generate_batch_with(
+    config: &GenConfig,
+    count: usize,
+    run: &Run,
+) -> Result<Vec<Puzzle>, GenError> {
+    let rng = &mut config.rng();
+    let mut seen = HashSet::new();
+    let mut puzzles = Vec::with_capacity(count);
+    let mut duplicates = 0;
+    let mut checked = 0;
+
+    while puzzles.len() < count {
+        let puzzle = generate_puzzle_with(config, rng, run)?;
+        let unique = seen.insert(fingerprint(&puzzle));
+
+        checked += 1;
+        if !unique {
+            duplicates += 1;
+            run.report.borrow_mut().duplicates = duplicates;
+        }
+        run.notify(Progress::DuplicateCheck {
+            checked,
+            duplicates,
+        });
+
+        // Small masks may not have enough distinct puzzles in them, the grids run out eventually
+        if unique {
+            puzzles.push(puzzle);
+        }
+    }
+
+    Ok(puzzles)
+}
+
+/// The steps of grid generation one at a time, for the benchmarks. Not a stable API
+#[doc(hidden)]
+pub mod stages {
+    use rand::Rng;
+
+    use super::{Corner, GenConfig, Grid, Run};
+
+    /// A grid part way through generation
+    #[derive(Clone)]
+    pub struct PartialGrid {
+        grid: Grid,
+        active_corners: Vec<Corner>,
+    }
+
+    impl PartialGrid {
+        /// `fix_up` only works on connected grids
+        pub fn is_connected(&self) -> bool {
+            self.grid.check_is_connected()
+        }
+
+        /// `true` marks a fillable cell
+        pub fn shape(&self) -> Vec<Vec<bool>> {
+            self.grid.to_bool_vec()
+        }
+    }
+
+    pub fn place_anchors(config: &GenConfig, rng: &mut impl Rng) -> PartialGrid {
+        let (grid, active_corners) = Grid::place_anchors(config, rng);
+
+        PartialGrid {
+            grid,
+            active_corners,
+        }
+    }
+
+    pub fn grow(partial: &mut PartialGrid, rng: &mut impl Rng) {
+        partial
+            .grid
+            .grow(&mut partial.active_corners, rng, &Run::unlimited())
+            .expect("Unlimited run ran out");
+    }
+
+    pub fn fix_up(partial: &mut PartialGrid) {
+        partial
+            .grid
+            .fix_up(&Run::unlimited())
+            .expect("Unlimited run ran out");
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::anchors::{DiagonalBand, FixedAnchors, Lattice, RandomScatter};
 
     #[test]
-    fn test_component_get_cells() {
+    fn test_component_cell_at() {
         let component = Component::new(5, 5);
 
         let expected = [
@@ -795,7 +1407,11 @@ mod tests {
             ],
         ];
 
-        assert_eq!(component.get_cells(), expected)
+        let cells: Vec<Vec<GGCell>> = (0..7)
+            .map(|y| (0..7).map(|x| component.cell_at(x, y)).collect())
+            .collect();
+
+        assert_eq!(cells, expected)
     }
 
     #[test]
@@ -831,7 +1447,350 @@ mod tests {
         // The grid generation doesn't need to be incredibly efficient but shouldn't take forever. If this starts
         // taking an annoying amount of time I should rethink the performance.
         for _ in 0..100 {
-            let _grid = Grid::generate(20, 20);
+            let _grid = Grid::generate(&GenConfig::new(20, 20)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_anchor_strategies() {
+        let configs = [
+            GenConfig::new(20, 20).with_anchors(JitteredLattice { jitter: 1 }),
+            GenConfig::new(20, 20).with_anchors(RandomScatter { count: 9 }),
+            GenConfig::new(20, 20).with_anchors(DiagonalBand { spread: 2 }),
+            GenConfig::new(8, 8).with_anchors(FixedAnchors(vec![
+                Anchor::new(0, 0, Component::new(2, 2)),
+                Anchor::new(6, 0, Component::new(2, 2)),
+                Anchor::new(0, 6, Component::new(2, 2)),
+                Anchor::new(6, 6, Component::new(2, 2)),
+            ])),
+        ];
+
+        for config in configs {
+            for _ in 0..10 {
+                let grid = Grid::generate(&config).unwrap();
+
+                assert!(grid.check_is_connected());
+                assert!(grid.get_cages().iter().all(|c| (2..=9).contains(&c.3)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bad_anchors_skipped() {
+        let bad = [
+            Anchor::new(250, 0, Component::new(10, 2)),
+            Anchor::new(0, 255, Component::new(2, 255)),
+            Anchor::new(3, 3, Component::new(0, 3)),
+            Anchor::new(3, 3, Component::new(1, 1)),
+        ];
+        let config = GenConfig::new(8, 8).with_anchors(FixedAnchors(
+            bad.into_iter()
+                .chain(Lattice.place_anchors(8, 8, &mut rng()))
+                .collect(),
+        ));
+
+        for _ in 0..10 {
+            assert!(Grid::generate(&config).unwrap().check_is_connected());
+        }
+
+        // Nothing fits, so there's nothing to grow from
+        let config = GenConfig::new(8, 8).with_anchors(FixedAnchors(bad.to_vec()));
+        assert_eq!(
+            Grid::generate(&config).err(),
+            Some(GenError::AttemptsExhausted)
+        );
+    }
+
+    #[test]
+    fn test_mask() {
+        // Diamond-ish shape, everything outside of it is forbidden
+        let mask = Mask::from_rows(
+            (0..20)
+                .map(|y: i32| {
+                    (0..20)
+                        .map(|x: i32| (x - 10).abs() + (y - 10).abs() < 12)
+                        .collect()
+                })
+                .collect(),
+        );
+        let config = GenConfig::new(20, 20).with_mask(mask.clone());
+
+        for _ in 0..10 {
+            let grid = Grid::generate(&config).unwrap();
+
+            assert!(grid.check_is_connected());
+            assert!(grid.get_cages().iter().all(|c| (2..=9).contains(&c.3)));
+            for (y, row) in grid.to_bool_vec().iter().enumerate() {
+                for (x, &fillable) in row.iter().enumerate() {
+                    assert!(mask.is_allowed(x, y) || !fillable);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mask_errors() {
+        let checkerboard = Mask::from_rows(
+            (0..10)
+                .map(|y| (0..10).map(|x| (x + y) % 2 == 0).collect())
+                .collect(),
+        );
+
+        assert_eq!(
+            Grid::generate(&GenConfig::new(10, 10).with_mask(checkerboard)).err(),
+            Some(GenError::MaskUnusable)
+        );
+        assert_eq!(
+            Grid::generate(&GenConfig::new(10, 10).with_mask(Mask::new(8, 10))).err(),
+            Some(GenError::MaskSizeMismatch {
+                expected: (10, 10),
+                found: (8, 10)
+            })
+        );
+
+        // Running out of budget is only the mask's fault if it kept every grid from being accepted
+        let masked = GenConfig::new(10, 10).with_mask(Mask::from_rows(vec![vec![true; 10]; 10]));
+        assert_eq!(
+            Grid::generate(&masked.clone().with_max_attempts(0)).err(),
+            Some(GenError::AttemptsExhausted)
+        );
+
+        let run = Run::unlimited();
+        assert_eq!(run.exhausted(&masked), GenError::AttemptsExhausted);
+
+        run.report.borrow_mut().grids.push(GridReport {
+            outcome: GridOutcome::Disconnected,
+            ..Default::default()
+        });
+        assert_eq!(run.exhausted(&masked), GenError::MaskUnusable);
+        assert_eq!(
+            run.exhausted(&GenConfig::new(10, 10)),
+            GenError::AttemptsExhausted
+        );
+
+        // A grid that failed to fill or made a duplicate was still accepted
+        run.report.borrow_mut().grids.push(GridReport {
+            outcome: GridOutcome::Accepted,
+            ..Default::default()
+        });
+        assert_eq!(run.exhausted(&masked), GenError::AttemptsExhausted);
+    }
+
+    #[test]
+    fn test_seed() {
+        let config = GenConfig::new(12, 12)
+            .with_anchors(RandomScatter { count: 6 })
+            .with_seed(42);
+
+        assert_eq!(generate_puzzle(&config), generate_puzzle(&config));
+        assert_eq!(
+            generate_batch(&config, 3).unwrap(),
+            generate_batch(&config, 3).unwrap()
+        );
+        assert_ne!(
+            generate_puzzle(&config),
+            generate_puzzle(&config.clone().with_seed(43))
+        );
+    }
+
+    #[test]
+    fn test_cut_cells() {
+        for seed in 0..20 {
+            let mut grid = Grid::generate(&GenConfig::new(12, 12).with_seed(seed)).unwrap();
+            let cut = grid.cut_cells();
+
+            // A cell is a cut cell exactly when blocking it disconnects the grid
+            for y in 0..12 {
+                for x in 0..12 {
+                    let i = grid.index(x, y);
+                    let cell = grid.cells[i];
+
+                    if !cell.is_fillable() {
+                        assert!(!cut.contains(i));
+                        continue;
+                    }
+
+                    grid.set(i, GGCell::Blocked);
+                    assert_eq!(cut.contains(i), !grid.check_is_connected(), "{} {}", x, y);
+                    grid.set(i, cell);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_budget() {
+        let token = CancelToken::new();
+        token.cancel();
+
+        assert_eq!(
+            generate_puzzle(&GenConfig::new(20, 20).with_cancel(token.clone())),
+            Err(GenError::Cancelled)
+        );
+        assert_eq!(
+            generate_grid(&GenConfig::new(20, 20).with_time_limit(Duration::ZERO)),
+            Err(GenError::TimedOut)
+        );
+        assert_eq!(
+            generate_grid(&GenConfig::new(20, 20).with_max_attempts(0)),
+            Err(GenError::AttemptsExhausted)
+        );
+
+        // The attempt limit covers the whole call, not each stage
+        let unfillable = GenConfig::new(8, 8)
+            .with_anchors(FixedAnchors(vec![]))
+            .with_max_attempts(1);
+        let (result, report) = generate_puzzle_report(&unfillable);
+        assert_eq!(result, Err(GenError::AttemptsExhausted));
+        assert_eq!(report.grids.len(), 1);
+        assert_eq!(generate_batch_report(&unfillable, 3).1.grids.len(), 3);
+
+        let (result, report) = generate_puzzle_report(&GenConfig::new(12, 12).with_max_attempts(1));
+        assert!(report.grids.len() <= 1);
+        assert_eq!(
+            result.is_ok(),
+            report.fill_attempts == 1 && report.failed_fills == 0
+        );
+
+        // A generous limit changes nothing
+        let config = GenConfig::new(12, 12).with_seed(7);
+        assert_eq!(
+            generate_puzzle(&config.clone().with_time_limit(Duration::from_secs(60))),
+            generate_puzzle(&config)
+        );
+
+        // Cancelling from another thread stops a batch part way
+        let token = CancelToken::new();
+        let config = GenConfig::new(30, 30).with_cancel(token.clone());
+        let batch = std::thread::spawn(move || generate_batch(&config, 100_000));
+        std::thread::sleep(Duration::from_millis(50));
+        token.cancel();
+        assert_eq!(batch.join().unwrap(), Err(GenError::Cancelled));
+    }
+
+    #[test]
+    fn test_progress() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let config = GenConfig::new(10, 10).with_seed(3);
+        let observed = config
+            .clone()
+            .with_progress(move |p| sink.lock().unwrap().push(p));
+
+        assert_eq!(
+            generate_batch(&observed, 3).unwrap(),
+            generate_batch(&config, 3).unwrap()
+        );
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0],
+            Progress::AnchorsPlaced {
+                attempt: 1,
+                anchors: 9
+            }
+        );
+
+        let count = |f: fn(&Progress) -> bool| events.iter().filter(|p| f(p)).count();
+        let grids = count(|p| matches!(p, Progress::AnchorsPlaced { .. }));
+        assert_eq!(
+            grids,
+            count(|p| matches!(p, Progress::GrowthFinished { .. }))
+        );
+        assert_eq!(
+            count(|p| matches!(p, Progress::FillAttempt { filled: true, .. })),
+            3
+        );
+
+        // Each filled puzzle is checked until it has one solution, the counts running on across the batch
+        let checks: Vec<(usize, bool)> = events
+            .iter()
+            .filter_map(|p| match *p {
+                Progress::UniquenessCheck { checked, unique } => Some((checked, unique)),
+                _ => None,
+            })
+            .collect();
+        assert!(checks.iter().map(|c| c.0).eq(1..=checks.len()));
+        assert_eq!(checks.iter().filter(|c| c.1).count(), 3);
+
+        let Some(&Progress::DuplicateCheck {
+            checked,
+            duplicates,
+        }) = events.last()
+        else {
+            panic!("Batch didn't end on a duplicate check: {:?}", events.last());
+        };
+        assert_eq!(checked - duplicates, 3);
+        assert!(events.iter().any(
+            |p| matches!(p, Progress::FixUpPass { attempt, pass: 2, .. } if *attempt == grids)
+        ));
+    }
+
+    #[test]
+    fn test_report() {
+        let config = GenConfig::new(10, 10).with_seed(3);
+        let (puzzles, report) = generate_batch_report(&config, 3);
+
+        assert_eq!(puzzles.unwrap(), generate_batch(&config, 3).unwrap());
+        assert_eq!(
+            report.fill_attempts - report.failed_fills - report.ambiguous,
+            3 + report.duplicates
+        );
+
+        // Every fill attempt is on a grid that was accepted
+        let accepted = report
+            .grids
+            .iter()
+            .filter(|g| g.outcome == GridOutcome::Accepted)
+            .count();
+        assert_eq!(accepted, report.fill_attempts);
+        assert_eq!(report.grids[0].anchors, 9);
+        for grid in &report.grids {
+            assert!(grid.wasted_iterations <= grid.growth_iterations);
+            assert_eq!(
+                grid.components,
+                grid.growth_iterations - grid.wasted_iterations
+            );
+        }
+
+        // Cancelled before starting, so nothing was tried
+        let token = CancelToken::new();
+        token.cancel();
+        let (result, report) = generate_grid_report(&config.with_cancel(token));
+        assert_eq!(result, Err(GenError::Cancelled));
+        assert!(report.grids.is_empty());
+        assert_eq!(report.retries(), 0);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(200))]
+
+        #[test]
+        fn prop_generate(width in 5u8..=30, height in 5u8..=30, seed: u64) {
+            let result = Grid::generate(&GenConfig::new(width, height).with_seed(seed));
+
+            if width < 8 || height < 8 {
+                prop_assert_eq!(result.err(), Some(GenError::UnsupportedSize { width, height }));
+                return Ok(());
+            }
+
+            prop_assert!(result.is_ok(), "{}x{}: {:?}", width, height, result.as_ref().err());
+            let grid = result.unwrap();
+
+            prop_assert!(grid.check_is_connected());
+
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let i = grid.index(x, y);
+                    prop_assert_ne!(grid.cells[i], GGCell::Uninitialized, "{} {}\n{}", x, y, grid);
+
+                    if grid.fillable.contains(i) {
+                        let (across, down) = grid.run_lengths_at(i);
+                        prop_assert!((2..=9).contains(&across), "{} {}\n{}", x, y, grid);
+                        prop_assert!((2..=9).contains(&down), "{} {}\n{}", x, y, grid);
+                    }
+                }
+            }
         }
     }
 }
@@ -1,2 +1,25 @@
-mod anchors;
+pub mod anchors;
+pub mod candidates;
+pub mod combinations;
+pub mod daily;
+pub mod error;
+pub mod fill;
+pub mod fingerprint;
 pub mod grid_gen;
+pub mod mask;
+pub mod mistakes;
+pub mod pdf;
+pub mod pool;
+pub mod progress;
+pub mod puzzle;
+mod render;
+pub mod report;
+pub mod save;
+pub mod share;
+pub mod solver;
+pub mod svg;
+pub mod text;
+pub mod thumbnail;
+pub mod trace;
+pub mod uniqueness;
+pub mod validate;
@@ -1,6 +1,5 @@
 use generator::grid_gen;
 
 fn main() {
-    println!("Hello, world!");
-    let grid = grid_gen::get_grid(30, 30);
+    let _grid = grid_gen::get_grid(30, 30);
 }
@@ -0,0 +1,336 @@
+use std::{fs, path::Path};
+
+use crate::error::ImageError;
+
+/// Marks which cells of the grid generation is allowed to make fillable, for themed shapes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    width: u8,
+    height: u8,
+    rows: Vec<Vec<bool>>,
+}
+
+impl Mask {
+    /// Mask allowing every cell
+    pub fn new(width: u8, height: u8) -> Self {
+        Self {
+            width,
+            height,
+            rows: vec![vec![true; width as usize]; height as usize],
+        }
+    }
+
+    /// Builds a mask from rows of cells, `true` meaning allowed. Panics if the rows differ in length
+    pub fn from_rows(rows: Vec<Vec<bool>>) -> Self {
+        let width = rows.first().map_or(0, |r| r.len());
+
+        if rows.iter().any(|r| r.len() != width) {
+            panic!("Mask rows must all be the same length");
+        }
+
+        Self {
+            width: width as u8,
+            height: rows.len() as u8,
+            rows,
+        }
+    }
+
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// Cells outside of the mask are never allowed
+    pub fn is_allowed(&self, x: usize, y: usize) -> bool {
+        self.rows
+            .get(y)
+            .and_then(|r| r.get(x))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, allowed: bool) {
+        self.rows[y][x] = allowed;
+    }
+
+    /// Loads a black-and-white PNG or PBM image and scales it to `width`x`height`. Dark, opaque pixels are allowed
+    /// cells, white or transparent ones are forbidden
+    pub fn from_image_file(
+        path: impl AsRef<Path>,
+        width: u8,
+        height: u8,
+    ) -> Result<Self, ImageError> {
+        Self::from_image_bytes(&fs::read(path)?, width, height)
+    }
+
+    /// Same as `from_image_file`, with the format detected from the file contents
+    pub fn from_image_bytes(bytes: &[u8], width: u8, height: u8) -> Result<Self, ImageError> {
+        let image = if bytes.starts_with(PNG_SIGNATURE) {
+            Bitmap::from_png(bytes)?
+        } else if bytes.starts_with(b"P1") || bytes.starts_with(b"P4") {
+            Bitmap::from_pbm(bytes)?
+        } else {
+            return Err(ImageError::UnknownFormat);
+        };
+
+        Ok(image.downsample(width, height))
+    }
+
+    /// Whether there is anywhere to put the smallest (2x2) component
+    pub(crate) fn has_room(&self) -> bool {
+        (1..self.height as usize).any(|y| {
+            (1..self.width as usize).any(|x| {
+                self.is_allowed(x - 1, y - 1)
+                    && self.is_allowed(x, y - 1)
+                    && self.is_allowed(x - 1, y)
+                    && self.is_allowed(x, y)
+            })
+        })
+    }
+}
+
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Source image reduced to one bit per pixel, `true` being dark
+struct Bitmap {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Bitmap {
+    fn from_png(bytes: &[u8]) -> Result<Self, ImageError> {
+        let mut decoder = png::Decoder::new(bytes);
+        // Palettes, low bit depths and 16 bit channels all become plain 8 bit channels
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let channels = info.color_type.samples();
+        let has_alpha = matches!(
+            info.color_type,
+            png::ColorType::GrayscaleAlpha | png::ColorType::Rgba
+        );
+
+        let pixels = buf[..info.buffer_size()]
+            .chunks_exact(info.line_size)
+            .flat_map(|line| line.chunks_exact(channels).take(info.width as usize))
+            .map(|px| {
+                let luma = if channels >= 3 {
+                    (px[0] as u32 * 299 + px[1] as u32 * 587 + px[2] as u32 * 114) / 1000
+                } else {
+                    px[0] as u32
+                };
+                let opaque = !has_alpha || px[channels - 1] >= 128;
+
+                opaque && luma < 128
+            })
+            .collect();
+
+        Ok(Self {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        })
+    }
+
+    fn from_pbm(bytes: &[u8]) -> Result<Self, ImageError> {
+        let invalid = |reason: &str| ImageError::InvalidPbm(reason.to_string());
+
+        // Header is the magic number, width and height separated by whitespace, with # starting a comment
+        let mut pos = 2;
+        let mut header = [0usize; 2];
+
+        for value in header.iter_mut() {
+            loop {
+                match bytes.get(pos) {
+                    Some(b'#') => {
+                        while bytes.get(pos).is_some_and(|&b| b != b'\n') {
+                            pos += 1;
+                        }
+                    }
+                    Some(b) if b.is_ascii_whitespace() => pos += 1,
+                    _ => break,
+                }
+            }
+
+            let start = pos;
+            while bytes.get(pos).is_some_and(|b| b.is_ascii_digit()) {
+                pos += 1;
+            }
+
+            *value = std::str::from_utf8(&bytes[start..pos])
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| invalid("bad width or height"))?;
+        }
+
+        let [width, height] = header;
+        if width == 0 || height == 0 {
+            return Err(invalid("width and height must be at least 1"));
+        }
+
+        let size = width
+            .checked_mul(height)
+            .ok_or_else(|| invalid("image is too large"))?;
+        let body = bytes.get(pos + 1..).unwrap_or_default();
+
+        let pixels: Vec<bool> = if bytes[1] == b'4' {
+            // Binary, each row is padded to a whole byte
+            let row_bytes = width.div_ceil(8);
+            if row_bytes
+                .checked_mul(height)
+                .is_none_or(|len| body.len() < len)
+            {
+                return Err(invalid("not enough pixel data"));
+            }
+
+            body.chunks_exact(row_bytes)
+                .take(height)
+                .flat_map(|row| (0..width).map(move |x| row[x / 8] & (0x80 >> (x % 8)) != 0))
+                .collect()
+        } else {
+            body.iter()
+                .filter(|b| !b.is_ascii_whitespace())
+                .take(size)
+                .map(|b| match b {
+                    b'0' => Ok(false),
+                    b'1' => Ok(true),
+                    _ => Err(invalid("pixels must be 0 or 1")),
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        if pixels.len() != size {
+            return Err(invalid("not enough pixel data"));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Each cell is allowed if at least half of the pixels it covers are dark
+    fn downsample(&self, width: u8, height: u8) -> Mask {
+        let mut mask = Mask::new(width, height);
+
+        // Source pixel range covered by cell `i` of `n`, always at least one pixel wide
+        let span = |i: usize, n: usize, len: usize| {
+            let start = i * len / n;
+            start..((i + 1) * len / n).max(start + 1).min(len)
+        };
+
+        for y in 0..height as usize {
+            let ys = span(y, height as usize, self.height);
+
+            for x in 0..width as usize {
+                let xs = span(x, width as usize, self.width);
+
+                let total = ys.len() * xs.len();
+                let dark = ys
+                    .clone()
+                    .flat_map(|py| xs.clone().map(move |px| (px, py)))
+                    .filter(|&(px, py)| self.pixels[py * self.width + px])
+                    .count();
+
+                mask.set(x, y, total != 0 && dark * 2 >= total);
+            }
+        }
+
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_room() {
+        let mut mask = Mask::from_rows(vec![
+            vec![true, false, true],
+            vec![false, true, false],
+            vec![true, true, true],
+        ]);
+
+        assert!(!mask.has_room());
+        assert!(!mask.is_allowed(3, 0));
+
+        mask.set(0, 1, true);
+
+        assert!(mask.has_room());
+    }
+
+    #[test]
+    fn test_from_pbm() {
+        // 4x4 image with the left half dark, scaled down and up
+        let ascii = b"P1\n# comment\n4 4\n1 1 0 0\n1 1 0 0\n1 1 0 0\n1 1 0 0\n";
+        let binary = b"P4 4 4\n\xc0\xc0\xc0\xc0";
+
+        for bytes in [&ascii[..], &binary[..]] {
+            assert_eq!(
+                Mask::from_image_bytes(bytes, 2, 2).unwrap(),
+                Mask::from_rows(vec![vec![true, false], vec![true, false]])
+            );
+            assert_eq!(
+                Mask::from_image_bytes(bytes, 8, 1).unwrap(),
+                Mask::from_rows(vec![vec![
+                    true, true, true, true, false, false, false, false
+                ]])
+            );
+        }
+
+        assert!(matches!(
+            Mask::from_image_bytes(b"P1 4 4 1 1 1", 2, 2),
+            Err(ImageError::InvalidPbm(_))
+        ));
+        assert!(matches!(
+            Mask::from_image_bytes(b"GIF89a", 2, 2),
+            Err(ImageError::UnknownFormat)
+        ));
+
+        // Empty and absurdly large images are errors rather than panics
+        for bytes in [
+            &b"P4 0 5\n"[..],
+            b"P1 5 0\n",
+            b"P4 18446744073709551615 2\n\xff",
+            b"P1 18446744073709551615 2\n1",
+        ] {
+            assert!(matches!(
+                Mask::from_image_bytes(bytes, 2, 2),
+                Err(ImageError::InvalidPbm(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_from_png() {
+        // 2x2 RGBA image: black, white / transparent black, black
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 2, 2);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[
+                    0, 0, 0, 255, 255, 255, 255, 255, //
+                    0, 0, 0, 0, 0, 0, 0, 255,
+                ])
+                .unwrap();
+        }
+
+        assert_eq!(
+            Mask::from_image_bytes(&bytes, 2, 2).unwrap(),
+            Mask::from_rows(vec![vec![true, false], vec![false, true]])
+        );
+    }
+}
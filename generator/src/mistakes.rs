@@ -0,0 +1,145 @@
+use crate::{puzzle::Puzzle, solver::Solver, uniqueness::find_solutions};
+
+/// Result of checking the player's digits against the solution
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkCheck {
+    /// Filled cells whose digit doesn't match the solution, row by row
+    pub incorrect: Vec<(u8, u8)>,
+    /// Filled cells whose digit matches the solution, row by row
+    pub correct: Vec<(u8, u8)>,
+    /// The incorrect cell that comes first in the logical solve order, the likeliest root of the other mistakes
+    pub first_divergence: Option<(u8, u8)>,
+}
+
+impl WorkCheck {
+    pub fn has_mistakes(&self) -> bool {
+        !self.incorrect.is_empty()
+    }
+}
+
+/// Checks the player's work on one puzzle. The solution and solve order are worked out once, so checking again after
+/// every move is cheap
+#[derive(Clone, Debug)]
+pub struct WorkChecker {
+    cells: Vec<(u8, u8)>,
+    solution: Vec<Vec<u8>>,
+    /// Cells in the order the logical solver fills them in from an empty board
+    order: Vec<(u8, u8)>,
+}
+
+impl WorkChecker {
+    /// `None` if the puzzle's rules don't have exactly one solution, as a player could then be right without matching
+    /// the stored one
+    pub fn new(puzzle: &Puzzle) -> Option<Self> {
+        let mut solutions = find_solutions(puzzle, 2)?;
+        if solutions.len() != 1 {
+            return None;
+        }
+
+        let mut solver = Solver::new(puzzle, &[]);
+        let order = std::iter::from_fn(|| solver.next_step())
+            .filter_map(|s| s.placed.map(|(x, y, _)| (x, y)))
+            .collect();
+
+        Some(Self {
+            cells: puzzle.fillable_cells().collect(),
+            solution: solutions.remove(0),
+            order,
+        })
+    }
+
+    /// Checks the player's digits, 0 being an empty cell
+    pub fn check(&self, digits: &[Vec<u8>]) -> WorkCheck {
+        let digit_at = |x: u8, y: u8| {
+            digits
+                .get(y as usize)
+                .and_then(|r| r.get(x as usize))
+                .copied()
+                .unwrap_or(0)
+        };
+
+        let mut check = WorkCheck::default();
+
+        for &(x, y) in &self.cells {
+            match digit_at(x, y) {
+                0 => {}
+                d if d == self.solution[y as usize][x as usize] => check.correct.push((x, y)),
+                _ => check.incorrect.push((x, y)),
+            }
+        }
+
+        if check.has_mistakes() {
+            check.first_divergence = self
+                .order
+                .iter()
+                .copied()
+                .find(|c| check.incorrect.contains(c))
+                // Cells the solver never reaches come after all the others, row by row
+                .or_else(|| check.incorrect.first().copied());
+        }
+
+        check
+    }
+}
+
+/// Checks the player's digits, 0 being an empty cell, against the puzzle's only solution. `None` if it doesn't have
+/// exactly one. Use a `WorkChecker` to check the same puzzle more than once
+pub fn check_work(puzzle: &Puzzle, digits: &[Vec<u8>]) -> Option<WorkCheck> {
+    WorkChecker::new(puzzle).map(|c| c.check(digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        grid_gen::{GenConfig, generate_puzzle},
+        puzzle::fixtures::{ambiguous, solved, unsolved},
+    };
+
+    #[test]
+    fn test_check_work() {
+        let puzzle = solved();
+
+        let empty = vec![vec![0; 4]; 3];
+        assert_eq!(check_work(&puzzle, &empty), Some(WorkCheck::default()));
+
+        // The solver places the 7 before the 9, so that's where the player went wrong first
+        let digits = vec![vec![0; 4], vec![0, 8, 8, 0], vec![0, 8, 0, 0]];
+        let check = check_work(&puzzle, &digits).unwrap();
+        assert!(check.has_mistakes());
+        assert_eq!(check.incorrect, vec![(1, 1), (2, 1)]);
+        assert_eq!(check.correct, vec![(1, 2)]);
+        assert_eq!(check.first_divergence, Some((2, 1)));
+
+        // The rules are enough to work out the solution
+        assert_eq!(check_work(&unsolved(), &digits), Some(check));
+
+        // Either solution could be the player's, so neither can be called wrong
+        let digits = vec![vec![0; 3], vec![0, 2, 1], vec![0, 1, 2]];
+        assert_eq!(check_work(&ambiguous(), &digits), None);
+    }
+
+    #[test]
+    fn test_generated_board() {
+        let puzzle = generate_puzzle(&GenConfig::new(12, 12).with_seed(1)).unwrap();
+        let checker = WorkChecker::new(&puzzle).unwrap();
+        let solution = puzzle.solution.clone().unwrap();
+
+        let check = checker.check(&solution);
+        assert!(!check.has_mistakes());
+        assert_eq!(check.correct.len(), puzzle.fillable_cells().count());
+
+        // Two wrong digits, the one the solver reaches first being the divergence
+        let (a, b) = (checker.order[0], checker.order[10]);
+        let mut digits = solution;
+        for (x, y) in [a, b] {
+            let cell = &mut digits[y as usize][x as usize];
+            *cell = *cell % 9 + 1;
+        }
+
+        let check = checker.check(&digits);
+        assert_eq!(check.incorrect.len(), 2);
+        assert!(check.incorrect.contains(&a) && check.incorrect.contains(&b));
+        assert_eq!(check.first_divergence, Some(a));
+    }
+}
@@ -0,0 +1,324 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+use crate::{
+    puzzle::Puzzle,
+    render::{Canvas, Color, draw_puzzle},
+};
+
+/// Width of every digit in Helvetica, as a fraction of the font size
+const DIGIT_WIDTH: f32 = 0.556;
+// Distance from the middle of a digit to its baseline, as a fraction of the font size
+const DIGIT_HALF_HEIGHT: f32 = 0.35;
+
+const FONT: Name = Name(b"F1");
+
+/// A4 in points
+pub const A4: (f32, f32) = (595.0, 842.0);
+/// US Letter in points
+pub const LETTER: (f32, f32) = (612.0, 792.0);
+
+/// One puzzle in a booklet
+#[derive(Clone, Debug)]
+pub struct BookletEntry {
+    pub title: String,
+    pub difficulty: String,
+    pub puzzle: Puzzle,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BookletOptions {
+    /// Page width and height in points
+    pub page_size: (f32, f32),
+    pub margin: f32,
+    /// Columns and rows of puzzles on each puzzle page, 0 counting as 1
+    pub puzzles_per_page: (u8, u8),
+    /// Columns and rows of solutions on each answer page, 0 counting as 1
+    pub answers_per_page: (u8, u8),
+}
+
+impl Default for BookletOptions {
+    fn default() -> Self {
+        Self {
+            page_size: A4,
+            margin: 36.0,
+            puzzles_per_page: (2, 2),
+            answers_per_page: (3, 3),
+        }
+    }
+}
+
+struct PdfCanvas {
+    content: Content,
+    page_height: f32,
+}
+
+impl PdfCanvas {
+    fn new(page_height: f32) -> Self {
+        Self {
+            content: Content::new(),
+            page_height,
+        }
+    }
+
+    fn gray(color: Color) -> f32 {
+        match color {
+            Color::Black => 0.0,
+            Color::White => 1.0,
+        }
+    }
+
+    fn set_color(&mut self, color: Color) {
+        let v = Self::gray(color);
+
+        self.content.set_fill_rgb(v, v, v);
+        self.content.set_stroke_rgb(v, v, v);
+    }
+
+    /// Left-aligned text with its baseline at `y`
+    fn label(&mut self, x: f32, y: f32, size: f32, text: &str) {
+        self.set_color(Color::Black);
+
+        // The font uses WinAnsiEncoding, which matches Latin-1 outside of the control characters. Anything else would
+        // come out garbled
+        let bytes: Vec<u8> = text
+            .chars()
+            .map(|c| match c as u32 {
+                0x20..=0x7e | 0xa0..=0xff => c as u8,
+                _ => b'?',
+            })
+            .collect();
+
+        self.content
+            .begin_text()
+            .set_font(FONT, size)
+            .next_line(x, self.page_height - y)
+            .show(Str(&bytes))
+            .end_text();
+    }
+}
+
+impl Canvas for PdfCanvas {
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, fill: Color) {
+        let v = Self::gray(fill);
+
+        self.content
+            .set_fill_rgb(v, v, v)
+            .set_stroke_rgb(0.0, 0.0, 0.0)
+            .set_line_width(0.5)
+            .rect(x, self.page_height - y - height, width, height)
+            .fill_nonzero_and_stroke();
+    }
+
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: Color) {
+        self.set_color(color);
+        self.content
+            .set_line_width(width)
+            .move_to(from.0, self.page_height - from.1)
+            .line_to(to.0, self.page_height - to.1)
+            .stroke();
+    }
+
+    fn text(&mut self, x: f32, y: f32, size: f32, color: Color, text: &str) {
+        // Only ever used for numbers, which all have the same width
+        let width = text.len() as f32 * DIGIT_WIDTH * size;
+
+        self.set_color(color);
+        self.content
+            .begin_text()
+            .set_font(FONT, size)
+            .next_line(
+                x - width / 2.0,
+                self.page_height - y - DIGIT_HALF_HEIGHT * size,
+            )
+            .show(Str(text.as_bytes()))
+            .end_text();
+    }
+}
+
+/// Lays out pages of `entries` in grid slots, drawing each with `draw_entry(canvas, entry, slot)`
+fn layout_pages<'a>(
+    entries: &'a [BookletEntry],
+    options: &BookletOptions,
+    (cols, rows): (u8, u8),
+    mut draw_entry: impl FnMut(&mut PdfCanvas, &'a BookletEntry, Rect),
+) -> Vec<Content> {
+    let (page_w, page_h) = options.page_size;
+    // A page always has room for at least one entry
+    let (cols, rows) = (cols.max(1), rows.max(1));
+    let per_page = cols as usize * rows as usize;
+
+    let slot_w = (page_w - 2.0 * options.margin) / cols as f32;
+    let slot_h = (page_h - 2.0 * options.margin) / rows as f32;
+
+    entries
+        .chunks(per_page)
+        .map(|page_entries| {
+            let mut canvas = PdfCanvas::new(page_h);
+
+            for (i, entry) in page_entries.iter().enumerate() {
+                let x = options.margin + (i % cols as usize) as f32 * slot_w;
+                let y = options.margin + (i / cols as usize) as f32 * slot_h;
+
+                draw_entry(&mut canvas, entry, Rect::new(x, y, x + slot_w, y + slot_h));
+            }
+
+            canvas.content
+        })
+        .collect()
+}
+
+/// Draws the puzzle as large as fits in the slot below a header of `header_h`, centred horizontally
+fn draw_in_slot(
+    canvas: &mut PdfCanvas,
+    puzzle: &Puzzle,
+    digits: Option<&[Vec<u8>]>,
+    slot: Rect,
+    header_h: f32,
+) {
+    // Leave a gap between neighbouring slots
+    let padding = 8.0;
+    let avail_w = slot.x2 - slot.x1 - padding;
+    let avail_h = slot.y2 - slot.y1 - header_h - padding;
+
+    let cell_size = (avail_w / puzzle.width as f32).min(avail_h / puzzle.height as f32);
+    let x = slot.x1 + (avail_w - cell_size * puzzle.width as f32) / 2.0;
+
+    draw_puzzle(canvas, puzzle, digits, (x, slot.y1 + header_h), cell_size);
+}
+
+/// Renders a printable booklet: the puzzles several to a page with their titles and difficulties, followed by answer
+/// pages with the solutions
+pub fn render_booklet(entries: &[BookletEntry], options: &BookletOptions) -> Vec<u8> {
+    let puzzle_pages = layout_pages(
+        entries,
+        options,
+        options.puzzles_per_page,
+        |canvas, entry, slot| {
+            canvas.label(
+                slot.x1,
+                slot.y1 + 12.0,
+                12.0,
+                &format!("{} - {}", entry.title, entry.difficulty),
+            );
+            draw_in_slot(canvas, &entry.puzzle, None, slot, 20.0);
+        },
+    );
+
+    let answer_pages = layout_pages(
+        entries,
+        options,
+        options.answers_per_page,
+        |canvas, entry, slot| {
+            canvas.label(slot.x1, slot.y1 + 8.0, 8.0, &entry.title);
+            draw_in_slot(
+                canvas,
+                &entry.puzzle,
+                entry.puzzle.solution.as_deref(),
+                slot,
+                12.0,
+            );
+        },
+    );
+
+    let mut pdf = Pdf::new();
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+
+    let page_count = puzzle_pages.len() + answer_pages.len();
+    let page_ids: Vec<Ref> = (0..page_count)
+        .map(|i| Ref::new(4 + 2 * i as i32))
+        .collect();
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_count as i32);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let (page_w, page_h) = options.page_size;
+
+    for (content, &page_id) in puzzle_pages.into_iter().chain(answer_pages).zip(&page_ids) {
+        // Each page's content stream directly follows it
+        let content_id = Ref::new(page_id.get() + 1);
+
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, page_w, page_h));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().fonts().pair(FONT, font_id);
+        page.finish();
+
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_gen::{GenConfig, generate_batch, generate_puzzle};
+
+    #[test]
+    fn test_render_booklet() {
+        let entries: Vec<BookletEntry> = generate_batch(&GenConfig::new(8, 8), 5)
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, puzzle)| BookletEntry {
+                title: format!("Puzzle {}", i + 1),
+                difficulty: "Easy".to_string(),
+                puzzle,
+            })
+            .collect();
+
+        let pdf = render_booklet(&entries, &BookletOptions::default());
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-"));
+        // Two pages of four puzzles, one page of nine answers
+        assert!(text.contains("/Count 3"));
+        assert!(text.contains("(Puzzle 5 - Easy) Tj"));
+        assert!(text.contains("(Puzzle 5) Tj"));
+
+        let letter = render_booklet(
+            &entries,
+            &BookletOptions {
+                page_size: LETTER,
+                puzzles_per_page: (1, 1),
+                ..Default::default()
+            },
+        );
+        assert!(String::from_utf8_lossy(&letter).contains("/Count 6"));
+    }
+
+    #[test]
+    fn test_zero_per_page() {
+        let mut entries = vec![BookletEntry {
+            title: "Café №1".to_string(),
+            difficulty: "Easy".to_string(),
+            puzzle: generate_puzzle(&GenConfig::new(8, 8)).unwrap(),
+        }];
+        entries.push(entries[0].clone());
+
+        let pdf = render_booklet(
+            &entries,
+            &BookletOptions {
+                puzzles_per_page: (0, 2),
+                answers_per_page: (0, 0),
+                ..Default::default()
+            },
+        );
+
+        let text = String::from_utf8_lossy(&pdf);
+
+        // Treated as one column, so one page of puzzles and two of answers
+        assert!(text.contains("/Count 3"));
+        // "Café ?1", Latin-1 is kept and anything past it isn't
+        assert!(text.contains("<436166E9203F31> Tj"));
+    }
+}
@@ -0,0 +1,410 @@
+//! Puzzles generated ahead of time, so asking for one doesn't mean waiting for the generator.
+//!
+//! Puzzles are kept in a bucket per size and difficulty. Like every generated puzzle they have a single solution, and
+//! like the daily puzzle they're graded by the solver, only going into a bucket if they need the techniques its
+//! difficulty calls for.
+//!
+//! A worker thread keeps every bucket topped up, working on the emptiest bucket first and sleeping while they're all
+//! full. The pool can be saved to a file and reloaded on the next start. A pool file that can't be read is ignored,
+//! since it only holds puzzles the worker can make again.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    daily::{Difficulty, generate_graded},
+    error::GenError,
+    grid_gen::{CancelToken, GenConfig},
+    puzzle::Puzzle,
+};
+
+pub const POOL_VERSION: u32 = 3;
+
+/// Puzzles of one size and difficulty. The size is the grid's, before the clue row and column are added
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Bucket {
+    pub width: u8,
+    pub height: u8,
+    pub difficulty: Difficulty,
+}
+
+impl Bucket {
+    pub fn new(width: u8, height: u8, difficulty: Difficulty) -> Self {
+        Self {
+            width,
+            height,
+            difficulty,
+        }
+    }
+
+    /// A square bucket of the size the daily puzzle uses for `difficulty`
+    pub fn for_difficulty(difficulty: Difficulty) -> Self {
+        let size = difficulty.size();
+
+        Self::new(size, size, difficulty)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    /// Puzzles kept ready in each bucket
+    pub per_bucket: usize,
+    pub buckets: Vec<(Bucket, GenConfig)>,
+    /// Loaded from when the pool starts and written to when it's dropped
+    pub path: Option<PathBuf>,
+}
+
+impl PoolConfig {
+    pub fn new(per_bucket: usize) -> Self {
+        Self {
+            per_bucket,
+            buckets: Vec::new(),
+            path: None,
+        }
+    }
+
+    /// Adds a bucket, generated with the default settings
+    pub fn with_bucket(self, bucket: Bucket) -> Self {
+        self.with_bucket_config(
+            bucket.difficulty,
+            GenConfig::new(bucket.width, bucket.height),
+        )
+    }
+
+    /// Adds a bucket for `difficulty` and the size of `config`, generated with `config`. Its seed is ignored, as every
+    /// puzzle should be different
+    pub fn with_bucket_config(mut self, difficulty: Difficulty, mut config: GenConfig) -> Self {
+        config.seed = None;
+        let bucket = Bucket::new(config.width, config.height, difficulty);
+        self.buckets.push((bucket, config));
+        self
+    }
+
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+#[derive(Default)]
+struct State {
+    ready: HashMap<Bucket, VecDeque<Puzzle>>,
+    /// Buckets the worker gave up on, retried once a puzzle is asked for
+    failed: HashMap<Bucket, GenError>,
+    stopping: bool,
+}
+
+struct Shared {
+    config: PoolConfig,
+    state: Mutex<State>,
+    /// Wakes the worker when a puzzle is taken or the pool stops
+    wanted: Condvar,
+    /// Wakes callers waiting for a puzzle
+    filled: Condvar,
+    /// Stops a generation in progress when the pool stops
+    cancel: CancelToken,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("Puzzle pool lock poisoned")
+    }
+}
+
+/// Ready-made puzzles per bucket, refilled in the background. Safe to share between threads
+pub struct PuzzlePool {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl PuzzlePool {
+    /// Loads the pool file if there is one and starts refilling
+    pub fn start(config: PoolConfig) -> Self {
+        let mut state = State::default();
+
+        for (bucket, _) in &config.buckets {
+            state.ready.insert(*bucket, VecDeque::new());
+        }
+
+        if let Some(saved) = config.path.as_deref().and_then(load) {
+            for SavedBucket { bucket, puzzles } in saved.buckets {
+                if let Some(ready) = state.ready.get_mut(&bucket) {
+                    ready.extend(
+                        puzzles
+                            .into_iter()
+                            .take(config.per_bucket.saturating_sub(ready.len())),
+                    );
+                }
+            }
+        }
+
+        let shared = Arc::new(Shared {
+            config,
+            state: Mutex::new(state),
+            wanted: Condvar::new(),
+            filled: Condvar::new(),
+            cancel: CancelToken::new(),
+        });
+
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("puzzle-pool".to_string())
+                .spawn(move || refill(&shared))
+                .expect("Unable to start puzzle pool worker")
+        };
+
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// A ready puzzle from the bucket, or `None` straight away if it's empty or isn't in the pool
+    pub fn take(&self, bucket: Bucket) -> Option<Puzzle> {
+        self.take_timeout(bucket, Duration::ZERO)
+    }
+
+    /// A puzzle from the bucket, waiting up to `timeout` for one if it's empty. A timeout too long to add to the
+    /// current time, like `Duration::MAX`, waits until a puzzle is ready or the bucket fails
+    pub fn take_timeout(&self, bucket: Bucket, timeout: Duration) -> Option<Puzzle> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut state = self.shared.lock();
+
+        state.ready.get(&bucket)?;
+
+        if state.failed.remove(&bucket).is_some() {
+            self.shared.wanted.notify_one();
+        }
+
+        loop {
+            if let Some(puzzle) = state.ready.get_mut(&bucket).and_then(VecDeque::pop_front) {
+                self.shared.wanted.notify_one();
+                return Some(puzzle);
+            }
+
+            if state.failed.contains_key(&bucket) {
+                return None;
+            }
+
+            let filled = &self.shared.filled;
+            state = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return None;
+                    }
+
+                    filled
+                        .wait_timeout(state, left)
+                        .expect("Puzzle pool lock poisoned")
+                        .0
+                }
+                None => filled.wait(state).expect("Puzzle pool lock poisoned"),
+            };
+        }
+    }
+
+    /// Puzzles ready in the bucket
+    pub fn ready(&self, bucket: Bucket) -> usize {
+        self.shared
+            .lock()
+            .ready
+            .get(&bucket)
+            .map_or(0, VecDeque::len)
+    }
+
+    /// Why the worker stopped filling the bucket, if it did. Taking from the bucket has it try again
+    pub fn error(&self, bucket: Bucket) -> Option<GenError> {
+        self.shared.lock().failed.get(&bucket).cloned()
+    }
+
+    /// Writes the ready puzzles to the pool file, if there is one
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.shared.config.path else {
+            return Ok(());
+        };
+
+        let state = self.shared.lock();
+        let saved = SavedPool {
+            version: POOL_VERSION,
+            buckets: self
+                .shared
+                .config
+                .buckets
+                .iter()
+                .map(|(bucket, _)| SavedBucket {
+                    bucket: *bucket,
+                    puzzles: state.ready[bucket].iter().cloned().collect(),
+                })
+                .collect(),
+        };
+        drop(state);
+
+        // Write next to the file and move it into place, so a crash never leaves half a pool behind
+        let json = serde_json::to_string(&saved).expect("Unable to serialize puzzle pool");
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, json)?;
+        fs::rename(temp, path)
+    }
+}
+
+impl Drop for PuzzlePool {
+    fn drop(&mut self) {
+        self.shared.lock().stopping = true;
+        self.shared.cancel.cancel();
+        self.shared.wanted.notify_one();
+
+        if let Some(worker) = self.worker.take() {
+            // A panicking worker has nothing left to clean up
+            let _ = worker.join();
+        }
+
+        // Nothing to report a failure to, and the puzzles can be made again
+        let _ = self.save();
+    }
+}
+
+/// Worker loop, generating for the emptiest bucket until the pool stops
+fn refill(shared: &Shared) {
+    let per_bucket = shared.config.per_bucket;
+
+    loop {
+        let (bucket, config) = {
+            let mut state = shared.lock();
+
+            loop {
+                if state.stopping {
+                    return;
+                }
+
+                let emptiest = shared
+                    .config
+                    .buckets
+                    .iter()
+                    .filter(|(b, _)| !state.failed.contains_key(b))
+                    .map(|(b, c)| (b, c, state.ready[b].len()))
+                    .filter(|&(_, _, ready)| ready < per_bucket)
+                    .min_by_key(|&(_, _, ready)| ready);
+
+                if let Some((bucket, config, _)) = emptiest {
+                    break (*bucket, config.clone().with_cancel(shared.cancel.clone()));
+                }
+
+                state = shared
+                    .wanted
+                    .wait(state)
+                    .expect("Puzzle pool lock poisoned");
+            }
+        };
+
+        let result = generate_graded(&config, bucket.difficulty);
+        let mut state = shared.lock();
+
+        match result {
+            Ok(puzzle) => {
+                if let Some(ready) = state.ready.get_mut(&bucket) {
+                    ready.push_back(puzzle);
+                }
+            }
+            Err(GenError::Cancelled) => return,
+            Err(e) => {
+                state.failed.insert(bucket, e);
+            }
+        }
+
+        shared.filled.notify_all();
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedBucket {
+    bucket: Bucket,
+    puzzles: Vec<Puzzle>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPool {
+    version: u32,
+    buckets: Vec<SavedBucket>,
+}
+
+fn load(path: &Path) -> Option<SavedPool> {
+    let saved: SavedPool = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+
+    (saved.version == POOL_VERSION).then_some(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solver::grade, uniqueness::is_unique};
+
+    #[test]
+    fn test_refill() {
+        let easy = Bucket::for_difficulty(Difficulty::Easy);
+        let hard = Bucket::new(14, 10, Difficulty::Hard);
+        let pool = PuzzlePool::start(PoolConfig::new(2).with_bucket(easy).with_bucket(hard));
+
+        // Sized by the bucket, plus the clue row and column
+        let puzzle = pool.take_timeout(easy, Duration::from_secs(10)).unwrap();
+        assert_eq!((puzzle.width, puzzle.height), (9, 9));
+        let puzzle = pool.take_timeout(hard, Duration::MAX).unwrap();
+        assert_eq!((puzzle.width, puzzle.height), (15, 11));
+        assert!(is_unique(&puzzle));
+        assert!(Difficulty::Hard.accepts(grade(&puzzle)));
+        assert_eq!(pool.take(Bucket::new(14, 10, Difficulty::Expert)), None);
+
+        // Taking makes room for the worker to top the bucket back up
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pool.ready(easy) < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(pool.ready(easy), 2);
+        assert_eq!(pool.error(easy), None);
+    }
+
+    #[test]
+    fn test_failed_bucket() {
+        let config = GenConfig::new(8, 8).with_max_attempts(0);
+        let bucket = Bucket::new(8, 8, Difficulty::Easy);
+        let pool =
+            PuzzlePool::start(PoolConfig::new(1).with_bucket_config(Difficulty::Easy, config));
+
+        // Waiting with no deadline still ends once the bucket fails
+        assert_eq!(pool.take_timeout(bucket, Duration::MAX), None);
+        assert_eq!(pool.error(bucket), Some(GenError::AttemptsExhausted));
+    }
+
+    #[test]
+    fn test_persist() {
+        let path = std::env::temp_dir().join(format!("puzzle-pool-{}.json", std::process::id()));
+        let bucket = Bucket::for_difficulty(Difficulty::Medium);
+        let config = PoolConfig::new(3).with_bucket(bucket).with_path(&path);
+
+        let pool = PuzzlePool::start(config.clone());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pool.ready(bucket) < 3 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        drop(pool);
+
+        let saved = load(&path).unwrap();
+        assert_eq!(saved.buckets[0].puzzles.len(), 3);
+
+        // The saved puzzles are ready as soon as the pool starts, in the same order
+        let pool = PuzzlePool::start(config);
+        assert_eq!(pool.take(bucket).as_ref(), saved.buckets[0].puzzles.first());
+        drop(pool);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
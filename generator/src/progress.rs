@@ -0,0 +1,47 @@
+//! Progress reports from generation, for progress bars and logs.
+//!
+//! Generation tries grids until one works out, so there's no fixed amount of work to count towards. Reports give the
+//! attempt each stage belongs to, which is enough to show that generation is moving and roughly how hard it's finding
+//! the settings. Counts start at 1 and run across a whole call, including every puzzle of a batch.
+
+use std::fmt::Debug;
+
+/// A stage of generation that just finished
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Progress {
+    /// A new grid was started with this many anchors
+    AnchorsPlaced { attempt: usize, anchors: usize },
+    /// The growth loop ran out of room, having added this many components to the anchors
+    GrowthFinished { attempt: usize, components: usize },
+    /// A fix-up pass over the grid's runs finished. Pass 1 splits runs longer than 9 and pass 2 grows runs shorter
+    /// than 2, `changed` being how many cells it blocked or unblocked
+    FixUpPass {
+        attempt: usize,
+        pass: u8,
+        changed: usize,
+    },
+    /// A finished grid was filled with digits, which can fail on awkward shapes
+    FillAttempt { attempt: usize, filled: bool },
+    /// A filled puzzle's solutions were counted. Until it's `unique`, generation adds a clue or re-fills some cells
+    /// and checks again
+    UniquenessCheck { checked: usize, unique: bool },
+    /// A puzzle of a batch was checked against the ones before it for being a rotation or reflection of one
+    DuplicateCheck { checked: usize, duplicates: usize },
+}
+
+/// Told about each stage of generation as it finishes. Called on the generating thread, so it should return quickly
+pub trait ProgressObserver: Send + Sync {
+    fn on_progress(&self, progress: Progress);
+}
+
+impl<F: Fn(Progress) + Send + Sync> ProgressObserver for F {
+    fn on_progress(&self, progress: Progress) {
+        self(progress)
+    }
+}
+
+impl Debug for dyn ProgressObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressObserver")
+    }
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+
+/// One run of the puzzle, mirroring `Rule` in the game. `x` and `y` are the clue cell, the run's cells follow it to
+/// the right or below
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rule {
+    pub x: u8,
+    pub y: u8,
+    pub is_vertical: bool,
+    pub num_cells: u8,
+    /// The value all cells in the run add up to, 0 if not known yet
+    pub value: u8,
+}
+
+impl Rule {
+    pub const fn new(x: u8, y: u8, num_cells: u8, value: u8, is_vertical: bool) -> Self {
+        Self {
+            x,
+            y,
+            is_vertical,
+            num_cells,
+            value,
+        }
+    }
+
+    pub const fn new_vert(x: u8, y: u8, num_cells: u8, value: u8) -> Self {
+        Self::new(x, y, num_cells, value, true)
+    }
+
+    pub const fn new_horiz(x: u8, y: u8, num_cells: u8, value: u8) -> Self {
+        Self::new(x, y, num_cells, value, false)
+    }
+
+    /// Whether the clue and every cell of the run are inside a puzzle of this size
+    pub fn fits(&self, width: u8, height: u8) -> bool {
+        // Checked on the last cell, before anything works out coordinates that could overflow
+        let n = self.num_cells as usize;
+        let (x, y) = (self.x as usize, self.y as usize);
+        let (last_x, last_y) = if self.is_vertical {
+            (x, y + n)
+        } else {
+            (x + n, y)
+        };
+
+        last_x < width as usize && last_y < height as usize
+    }
+
+    /// Coordinates of the fillable cells in this run, in order
+    pub fn cells(&self) -> impl Iterator<Item = (u8, u8)> + use<> {
+        let Self {
+            x, y, is_vertical, ..
+        } = *self;
+
+        (1..=self.num_cells).map(move |i| if is_vertical { (x, y + i) } else { (x + i, y) })
+    }
+}
+
+/// A kakuro board laid out like the game's `Puzzle`: row and column 0 are always clue cells
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "PuzzleData", try_from = "PuzzleData")]
+pub struct Puzzle {
+    pub width: u8,
+    pub height: u8,
+    pub rules: Vec<Rule>,
+    /// Digit of every cell in the solved puzzle (0 for clue cells), if known
+    pub solution: Option<Vec<Vec<u8>>>,
+    fillable: Vec<Vec<bool>>,
+}
+
+/// Serialized form of `Puzzle`, which leaves out what can be worked out from the rules
+#[derive(Serialize, Deserialize)]
+struct PuzzleData {
+    width: u8,
+    height: u8,
+    rules: Vec<Rule>,
+    solution: Option<Vec<Vec<u8>>>,
+}
+
+impl From<Puzzle> for PuzzleData {
+    fn from(puzzle: Puzzle) -> Self {
+        Self {
+            width: puzzle.width,
+            height: puzzle.height,
+            rules: puzzle.rules,
+            solution: puzzle.solution,
+        }
+    }
+}
+
+impl TryFrom<PuzzleData> for Puzzle {
+    type Error = String;
+
+    fn try_from(data: PuzzleData) -> Result<Self, Self::Error> {
+        if let Some(rule) = data.rules.iter().find(|r| !r.fits(data.width, data.height)) {
+            return Err(format!(
+                "rule at {},{} goes outside the {}x{} puzzle",
+                rule.x, rule.y, data.width, data.height
+            ));
+        }
+
+        let solution_fits = |s: &Vec<Vec<u8>>| {
+            s.len() == data.height as usize && s.iter().all(|r| r.len() == data.width as usize)
+        };
+
+        if data.solution.as_ref().is_some_and(|s| !solution_fits(s)) {
+            return Err("solution doesn't match the puzzle size".to_string());
+        }
+
+        Ok(Self::new(
+            data.width,
+            data.height,
+            data.rules,
+            data.solution,
+        ))
+    }
+}
+
+impl Puzzle {
+    pub fn new(width: u8, height: u8, rules: Vec<Rule>, solution: Option<Vec<Vec<u8>>>) -> Self {
+        let mut fillable = vec![vec![false; width as usize]; height as usize];
+
+        for rule in &rules {
+            for (x, y) in rule.cells() {
+                fillable[y as usize][x as usize] = true;
+            }
+        }
+
+        Self {
+            width,
+            height,
+            rules,
+            solution,
+            fillable,
+        }
+    }
+
+    /// Builds an unsolved puzzle from a generated grid (`true` marking fillable cells), adding the clue row and column.
+    /// All rule values are 0
+    pub fn from_shape(shape: &[Vec<bool>]) -> Self {
+        let height = shape.len() as u8 + 1;
+        let width = shape.first().map_or(0, |r| r.len()) as u8 + 1;
+
+        let is_fillable = |x: u8, y: u8| {
+            x > 0 && y > 0 && shape[y as usize - 1].get(x as usize - 1) == Some(&true)
+        };
+
+        let mut rules = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                if is_fillable(x, y) {
+                    continue;
+                }
+
+                let down = (y + 1..height).take_while(|&ny| is_fillable(x, ny)).count();
+                if down > 0 {
+                    rules.push(Rule::new_vert(x, y, down as u8, 0));
+                }
+
+                let across = (x + 1..width).take_while(|&nx| is_fillable(nx, y)).count();
+                if across > 0 {
+                    rules.push(Rule::new_horiz(x, y, across as u8, 0));
+                }
+            }
+        }
+
+        Self::new(width, height, rules, None)
+    }
+
+    pub fn is_fillable(&self, x: u8, y: u8) -> bool {
+        self.fillable
+            .get(y as usize)
+            .and_then(|r| r.get(x as usize))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Coordinates of every fillable cell, row by row
+    pub fn fillable_cells(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_fillable(x, y))
+    }
+
+    /// The horizontal and vertical rule covering this cell, if any
+    pub fn rules_for(&self, x: u8, y: u8) -> (Option<&Rule>, Option<&Rule>) {
+        let find = |is_vertical: bool| {
+            self.rules
+                .iter()
+                .find(|r| r.is_vertical == is_vertical && r.cells().any(|c| c == (x, y)))
+        };
+
+        (find(false), find(true))
+    }
+
+    /// The down and across values of a clue cell, if it has rules going that way
+    pub fn clues_at(&self, x: u8, y: u8) -> (Option<u8>, Option<u8>) {
+        let find = |is_vertical: bool| {
+            self.rules
+                .iter()
+                .find(|r| r.x == x && r.y == y && r.is_vertical == is_vertical)
+                .map(|r| r.value)
+        };
+
+        (find(true), find(false))
+    }
+}
+
+/// Puzzles for the tests of several modules
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::Puzzle;
+
+    /// The puzzle of `unsolved` with its digits
+    pub(crate) const SOLVED: &str = "
+        #    17\\  11\\  #
+        \\16  9    7    #
+        \\12  8    4    #
+    ";
+
+    /// Two cells each for 16 and 17 only leave 9 for the top left cell, which decides the rest
+    pub(crate) fn unsolved() -> Puzzle {
+        Puzzle {
+            solution: None,
+            ..solved()
+        }
+    }
+
+    pub(crate) fn solved() -> Puzzle {
+        SOLVED.parse().unwrap()
+    }
+
+    /// 1 2 over 2 1 and the other way round both add up to 3 everywhere
+    pub(crate) fn ambiguous() -> Puzzle {
+        "
+            #   3\\  3\\
+            \\3  .   .
+            \\3  .   .
+        "
+        .parse()
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_shape() {
+        let shape = vec![
+            vec![true, true, false],
+            vec![true, true, true],
+            vec![false, true, true],
+        ];
+
+        let puzzle = Puzzle::from_shape(&shape);
+
+        assert_eq!((puzzle.width, puzzle.height), (4, 4));
+        assert_eq!(
+            puzzle.rules,
+            vec![
+                Rule::new_vert(1, 0, 2, 0),
+                Rule::new_vert(2, 0, 3, 0),
+                Rule::new_horiz(0, 1, 2, 0),
+                Rule::new_vert(3, 1, 2, 0),
+                Rule::new_horiz(0, 2, 3, 0),
+                Rule::new_horiz(1, 3, 2, 0),
+            ]
+        );
+        assert_eq!(puzzle.fillable_cells().count(), 7);
+        assert_eq!(
+            puzzle.rules_for(2, 2),
+            (
+                Some(&Rule::new_horiz(0, 2, 3, 0)),
+                Some(&Rule::new_vert(2, 0, 3, 0))
+            )
+        );
+        assert_eq!(puzzle.clues_at(0, 0), (None, None));
+        assert_eq!(puzzle.clues_at(3, 1), (Some(0), None));
+    }
+
+    #[test]
+    fn test_serde() {
+        let puzzle = Puzzle::new(
+            3,
+            3,
+            vec![Rule::new_vert(1, 0, 2, 17)],
+            Some(vec![vec![0, 0, 0], vec![0, 9, 0], vec![0, 8, 0]]),
+        );
+
+        let json = serde_json::to_string(&puzzle).unwrap();
+        assert!(!json.contains("fillable"));
+        assert_eq!(serde_json::from_str::<Puzzle>(&json).unwrap(), puzzle);
+
+        let outside = json.replace("\"num_cells\":2", "\"num_cells\":3");
+        assert!(serde_json::from_str::<Puzzle>(&outside).is_err());
+    }
+}
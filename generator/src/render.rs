@@ -0,0 +1,99 @@
+use crate::puzzle::Puzzle;
+
+/// Size of a cell in the game, which the clue offsets below are relative to
+pub(crate) const BASE_CELL_SIZE: f32 = 32.0;
+
+// Centre of the clue text within its cell at the game's cell size, matching `scripts/rules.gd`
+const VERT_ONLY_OFFSET: (f32, f32) = (17.0, 22.0);
+const HORIZ_ONLY_OFFSET: (f32, f32) = (22.0, 17.0);
+const VERT_OFFSET: (f32, f32) = (10.0, 22.0);
+const HORIZ_OFFSET: (f32, f32) = (23.0, 10.0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Color {
+    Black,
+    White,
+}
+
+/// Drawing surface shared by the output formats. Coordinates start at the top left and go down
+pub(crate) trait Canvas {
+    /// Filled rectangle with a black outline
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, fill: Color);
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: Color);
+    /// Text centred on `x` and `y`
+    fn text(&mut self, x: f32, y: f32, size: f32, color: Color, text: &str);
+}
+
+/// Draws the puzzle with its top left corner at `origin`. `digits` has one digit per cell, 0 for empty cells
+pub(crate) fn draw_puzzle(
+    canvas: &mut impl Canvas,
+    puzzle: &Puzzle,
+    digits: Option<&[Vec<u8>]>,
+    origin: (f32, f32),
+    cell_size: f32,
+) {
+    let scale = cell_size / BASE_CELL_SIZE;
+
+    for y in 0..puzzle.height {
+        for x in 0..puzzle.width {
+            let px = origin.0 + x as f32 * cell_size;
+            let py = origin.1 + y as f32 * cell_size;
+
+            if puzzle.is_fillable(x, y) {
+                canvas.rect(px, py, cell_size, cell_size, Color::White);
+
+                let digit = digits
+                    .and_then(|d| d.get(y as usize))
+                    .and_then(|r| r.get(x as usize))
+                    .copied()
+                    .unwrap_or(0);
+
+                if digit != 0 {
+                    canvas.text(
+                        px + cell_size / 2.0,
+                        py + cell_size / 2.0,
+                        cell_size * 0.6,
+                        Color::Black,
+                        &digit.to_string(),
+                    );
+                }
+
+                continue;
+            }
+
+            canvas.rect(px, py, cell_size, cell_size, Color::Black);
+
+            let (down, across) = puzzle.clues_at(x, y);
+            if down.is_none() && across.is_none() {
+                continue;
+            }
+
+            // Split between the down value (bottom left) and the across value (top right)
+            canvas.line(
+                (px, py),
+                (px + cell_size, py + cell_size),
+                scale,
+                Color::White,
+            );
+
+            let (down_offset, across_offset) = if down.is_some() && across.is_some() {
+                (VERT_OFFSET, HORIZ_OFFSET)
+            } else {
+                (VERT_ONLY_OFFSET, HORIZ_ONLY_OFFSET)
+            };
+
+            for (value, (dx, dy)) in [(down, down_offset), (across, across_offset)] {
+                // Shapes without values yet get an empty clue cell
+                if let Some(value @ 1..) = value {
+                    canvas.text(
+                        px + dx * scale,
+                        py + dy * scale,
+                        cell_size * 0.3,
+                        Color::White,
+                        &value.to_string(),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! What happened during a generation call, for tracking down slow or failing settings.
+//!
+//! The same details are also emitted as `tracing` spans and events at debug level, one span per call and per grid
+//! tried, for logging them as they happen instead.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// How a grid attempt ended
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GridOutcome {
+    Accepted,
+    /// Growth left the fillable cells in more than one piece
+    Disconnected,
+    /// Fix-up couldn't get every run to 2-9 cells
+    InvalidRuns,
+    /// The time limit ran out or generation was cancelled part way
+    #[default]
+    Interrupted,
+}
+
+/// One grid tried during a call
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct GridReport {
+    /// Anchors that fit on the grid
+    pub anchors: usize,
+    /// Components growth added to the anchors
+    pub components: usize,
+    /// Times growth picked a corner
+    pub growth_iterations: usize,
+    /// Times the picked corner had no room for a component
+    pub wasted_iterations: usize,
+    /// Corners still open when growth stopped. Only non-zero if growth gave up after too many wasted iterations in a
+    /// row
+    pub corners_remaining: usize,
+    /// Cells fix-up blocked to split runs longer than 9
+    pub runs_split: usize,
+    /// Cells fix-up unblocked to grow runs shorter than 2
+    pub runs_grown: usize,
+    pub outcome: GridOutcome,
+}
+
+/// Everything tried during one call, in order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct GenReport {
+    pub grids: Vec<GridReport>,
+    /// Grids filled with digits, including ones no fill was found for
+    pub fill_attempts: usize,
+    pub failed_fills: usize,
+    /// Times a filled puzzle's solutions were counted
+    pub uniqueness_checks: usize,
+    /// Cells turned into clues to take away a second solution
+    pub clues_added: usize,
+    /// Filled puzzles thrown away for still having more than one solution
+    pub ambiguous: usize,
+    /// Batch puzzles thrown away for being a rotation or reflection of an earlier one
+    pub duplicates: usize,
+    pub elapsed: Duration,
+}
+
+impl GenReport {
+    /// Grids, fills and puzzles that had to be tried again
+    pub fn retries(&self) -> usize {
+        let rejected = self
+            .grids
+            .iter()
+            .filter(|g| {
+                matches!(
+                    g.outcome,
+                    GridOutcome::Disconnected | GridOutcome::InvalidRuns
+                )
+            })
+            .count();
+
+        rejected + self.failed_fills + self.ambiguous + self.duplicates
+    }
+}
@@ -0,0 +1,332 @@
+//! Saves of in-progress puzzles, shared by the game, the web build and support tools.
+//!
+//! Saves are JSON objects with a `version` field. Older saves are upgraded on load by running them through
+//! `MIGRATIONS` in order, so every change to the format bumps `SAVE_VERSION` and adds a migration from the version
+//! before it.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{combinations::DigitSet, error::SaveError, puzzle::Puzzle};
+
+pub const SAVE_VERSION: u32 = 1;
+
+/// `MIGRATIONS[i]` upgrades a save from version `i + 1` to `i + 2`
+const MIGRATIONS: [fn(&mut Value); SAVE_VERSION as usize - 1] = [];
+
+/// Which puzzle the save is for
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PuzzleSource {
+    /// A puzzle from the library or the daily puzzle, looked up by id
+    Reference {
+        id: String,
+    },
+    Embedded {
+        puzzle: Puzzle,
+    },
+}
+
+/// Notes on one cell, as digits in ascending order
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellNotes {
+    pub x: u8,
+    pub y: u8,
+    pub digits: Vec<u8>,
+}
+
+/// One undoable change by the player
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Edit {
+    /// Digit changed from `old` to `new`, 0 being empty
+    Digit { x: u8, y: u8, old: u8, new: u8 },
+    /// Note toggled on or off
+    Note { x: u8, y: u8, digit: u8 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub puzzle: PuzzleSource,
+    /// Player's digit in every cell, 0 for empty and clue cells
+    pub digits: Vec<Vec<u8>>,
+    /// Only cells with at least one note, row by row
+    pub notes: Vec<CellNotes>,
+    pub elapsed_ms: u64,
+    /// Edits in the order they were made, the last one undone first
+    pub history: Vec<Edit>,
+}
+
+impl SaveGame {
+    /// Fresh save for `puzzle`, referring to it by `id` instead of embedding it if given
+    pub fn new(puzzle: &Puzzle, id: Option<String>) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            puzzle: match id {
+                Some(id) => PuzzleSource::Reference { id },
+                None => PuzzleSource::Embedded {
+                    puzzle: puzzle.clone(),
+                },
+            },
+            digits: vec![vec![0; puzzle.width as usize]; puzzle.height as usize],
+            notes: Vec::new(),
+            elapsed_ms: 0,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn notes_at(&self, x: u8, y: u8) -> DigitSet {
+        self.notes
+            .iter()
+            .find(|n| (n.x, n.y) == (x, y))
+            .map_or(DigitSet::EMPTY, |n| n.digits.iter().copied().collect())
+    }
+
+    /// Notes in the layout of `Cell.notes` in the game, index 0 being digit 1
+    pub fn godot_notes(&self, x: u8, y: u8) -> [bool; 9] {
+        let set = self.notes_at(x, y);
+
+        std::array::from_fn(|i| set.contains(i as u8 + 1))
+    }
+
+    /// Sets a cell's digit, 0 clearing it. Digits above 9 and cells outside the board are ignored
+    pub fn set_digit(&mut self, x: u8, y: u8, digit: u8) {
+        if digit > 9 {
+            return;
+        }
+
+        let Some(cell) = self.cell_mut(x, y) else {
+            return;
+        };
+
+        let old = std::mem::replace(cell, digit);
+
+        if old != digit {
+            self.history.push(Edit::Digit {
+                x,
+                y,
+                old,
+                new: digit,
+            });
+        }
+    }
+
+    /// Toggles a note on a cell. Digits outside 1-9 and cells outside the board are ignored
+    pub fn toggle_note(&mut self, x: u8, y: u8, digit: u8) {
+        if !(1..=9).contains(&digit) || self.cell_mut(x, y).is_none() {
+            return;
+        }
+
+        self.flip_note(x, y, digit);
+        self.history.push(Edit::Note { x, y, digit });
+    }
+
+    /// Reverts the last edit, returning it
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.history.pop()?;
+
+        match edit {
+            Edit::Digit { x, y, old, .. } => {
+                // Loaded saves could have edits outside the board
+                if let Some(cell) = self.cell_mut(x, y) {
+                    *cell = old;
+                }
+            }
+            Edit::Note { x, y, digit } => self.flip_note(x, y, digit),
+        }
+
+        Some(edit)
+    }
+
+    fn cell_mut(&mut self, x: u8, y: u8) -> Option<&mut u8> {
+        self.digits
+            .get_mut(y as usize)
+            .and_then(|r| r.get_mut(x as usize))
+    }
+
+    fn flip_note(&mut self, x: u8, y: u8, digit: u8) {
+        let set = self.notes_at(x, y);
+        let set = if set.contains(digit) {
+            set.without(digit)
+        } else {
+            set.with(digit)
+        };
+
+        self.notes.retain(|n| (n.x, n.y) != (x, y));
+
+        if !set.is_empty() {
+            let at = self.notes.partition_point(|n| (n.y, n.x) < (y, x));
+            self.notes.insert(
+                at,
+                CellNotes {
+                    x,
+                    y,
+                    digits: set.iter().collect(),
+                },
+            );
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // Notes are a list rather than a map keyed by cell, which JSON has no keys for
+        serde_json::to_string(self).expect("Unable to serialize save")
+    }
+
+    /// Reads a save of this or any older version, rejecting digits that don't fit an embedded puzzle
+    pub fn from_json(json: &str) -> Result<Self, SaveError> {
+        let mut value: Value = serde_json::from_str(json)?;
+
+        let version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or(SaveError::MissingVersion)?;
+
+        if version == 0 {
+            return Err(SaveError::MissingVersion);
+        }
+
+        if version > SAVE_VERSION as u64 {
+            return Err(SaveError::UnsupportedVersion(
+                version.try_into().unwrap_or(u32::MAX),
+            ));
+        }
+
+        migrate(&mut value, version as usize, &MIGRATIONS);
+
+        let save: Self = serde_json::from_value(value)?;
+
+        if let PuzzleSource::Embedded { puzzle } = &save.puzzle
+            && (save.digits.len() != puzzle.height as usize
+                || save.digits.iter().any(|r| r.len() != puzzle.width as usize))
+        {
+            return Err(SaveError::SizeMismatch);
+        }
+
+        Ok(save)
+    }
+}
+
+/// Upgrades a save of `version` to the version after the last of `migrations`
+fn migrate(value: &mut Value, version: usize, migrations: &[fn(&mut Value)]) {
+    for migration in &migrations[version - 1..] {
+        migration(value);
+    }
+
+    value["version"] = (migrations.len() + 1).into();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::fixtures::solved;
+
+    #[test]
+    fn test_edits() {
+        let mut save = SaveGame::new(&solved(), None);
+
+        save.set_digit(1, 1, 9);
+        save.set_digit(1, 1, 8);
+        save.toggle_note(2, 1, 7);
+        save.toggle_note(2, 1, 4);
+        save.toggle_note(1, 2, 8);
+        assert_eq!(save.notes_at(2, 1), DigitSet::from_iter([4, 7]));
+        assert!(save.godot_notes(1, 2)[7]);
+        assert_eq!(
+            save.notes.iter().map(|n| (n.x, n.y)).collect::<Vec<_>>(),
+            vec![(2, 1), (1, 2)]
+        );
+
+        // Edits that change nothing aren't recorded
+        save.set_digit(1, 1, 8);
+        save.set_digit(9, 9, 1);
+        save.set_digit(2, 2, 10);
+        save.set_digit(2, 2, 200);
+        assert_eq!(save.history.len(), 5);
+
+        save.undo();
+        save.undo();
+        assert_eq!(save.notes_at(1, 2), DigitSet::EMPTY);
+        assert_eq!(save.notes_at(2, 1), DigitSet::single(7));
+        save.undo();
+        save.undo();
+        assert_eq!(save.digits[1][1], 9);
+        save.undo();
+        assert_eq!(save.digits[1][1], 0);
+        assert_eq!(save.undo(), None);
+    }
+
+    #[test]
+    fn test_json() {
+        let mut save = SaveGame::new(&solved(), None);
+        save.set_digit(2, 2, 4);
+        save.toggle_note(1, 1, 9);
+        save.elapsed_ms = 61_500;
+
+        let json = save.to_json();
+        assert!(json.contains("\"kind\":\"embedded\""));
+        assert!(json.contains("{\"type\":\"digit\",\"x\":2,\"y\":2,\"old\":0,\"new\":4}"));
+        assert_eq!(SaveGame::from_json(&json).unwrap(), save);
+
+        let referenced = SaveGame::new(&solved(), Some("daily-2026-10-19".to_string()));
+        let json = referenced.to_json();
+        assert!(json.contains("\"id\":\"daily-2026-10-19\""));
+        assert_eq!(SaveGame::from_json(&json).unwrap(), referenced);
+
+        let newer = json.replace("\"version\":1", "\"version\":99");
+        assert!(matches!(
+            SaveGame::from_json(&newer),
+            Err(SaveError::UnsupportedVersion(99))
+        ));
+
+        let unversioned = json.replace("\"version\":1,", "");
+        assert!(matches!(
+            SaveGame::from_json(&unversioned),
+            Err(SaveError::MissingVersion)
+        ));
+        assert!(matches!(SaveGame::from_json("{"), Err(SaveError::Json(_))));
+
+        let mut wrong_size = SaveGame::new(&solved(), None);
+        wrong_size.digits[2].pop();
+        assert!(matches!(
+            SaveGame::from_json(&wrong_size.to_json()),
+            Err(SaveError::SizeMismatch)
+        ));
+        wrong_size.digits.pop();
+        assert!(matches!(
+            SaveGame::from_json(&wrong_size.to_json()),
+            Err(SaveError::SizeMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_migrate() {
+        // Version 1 had `time` in seconds, version 2 renamed it, version 3 added the history
+        let migrations: [fn(&mut Value); 2] = [
+            |v| {
+                let seconds = v["time"].take().as_u64().unwrap();
+                v["elapsed_ms"] = (seconds * 1000).into();
+            },
+            |v| v["history"] = Value::Array(Vec::new()),
+        ];
+
+        let mut v1 = serde_json::json!({ "version": 1, "time": 61 });
+        migrate(&mut v1, 1, &migrations);
+        assert_eq!(v1["version"], 3);
+        assert_eq!(v1["elapsed_ms"], 61_000);
+        assert_eq!(v1["history"], Value::Array(Vec::new()));
+
+        // Only the migrations after the save's version run
+        let mut v2 = serde_json::json!({ "version": 2, "elapsed_ms": 500 });
+        migrate(&mut v2, 2, &migrations);
+        assert_eq!(v2["elapsed_ms"], 500);
+        assert_eq!(v2["version"], 3);
+
+        let mut current = serde_json::json!({ "version": 3, "elapsed_ms": 500 });
+        migrate(&mut current, 3, &migrations);
+        assert_eq!(
+            current,
+            serde_json::json!({ "version": 3, "elapsed_ms": 500 })
+        );
+    }
+}
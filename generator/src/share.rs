@@ -0,0 +1,256 @@
+//! Short URL-safe codes for sending a puzzle to someone.
+//!
+//! A code is base64url (no padding) of these fields, bit-packed most significant bit first:
+//!
+//! | Field    | Bits                | Contents                                                        |
+//! |----------|---------------------|-----------------------------------------------------------------|
+//! | version  | 8                   | `CODE_VERSION`                                                  |
+//! | width    | 8                   | Including the clue column                                       |
+//! | height   | 8                   | Including the clue row                                          |
+//! | flags    | 8                   | Bit 0 set if the solution is included                           |
+//! | shape    | 1 per inner cell    | Fillable cells row by row, leaving out the clue row and column  |
+//! | sums     | 6 per rule          | Rule values in the order `Puzzle::from_shape` creates the rules |
+//! | solution | 4 per fillable cell | Row by row, only if the flag is set                             |
+//! | checksum | 16                  | Low bits of the CRC-32 of everything before, byte aligned       |
+//!
+//! Decoding gives back the same puzzle, with its rules in the order `Puzzle::from_shape` creates them.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+
+use crate::{error::ShareCodeError, puzzle::Puzzle};
+
+pub const CODE_VERSION: u8 = 1;
+
+const HAS_SOLUTION: u8 = 1;
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u32, bits: u32) {
+        for i in (0..bits).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+
+            if value >> i & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+
+            self.len += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: u32) -> Result<u32, ShareCodeError> {
+        let mut value = 0;
+
+        for _ in 0..bits {
+            let byte = self
+                .bytes
+                .get(self.pos / 8)
+                .ok_or_else(|| ShareCodeError::InvalidPuzzle("code ends early".to_string()))?;
+
+            value = value << 1 | (byte >> (7 - self.pos % 8) & 1) as u32;
+            self.pos += 1;
+        }
+
+        Ok(value)
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u16 {
+    crc32fast::hash(bytes) as u16
+}
+
+/// Encodes the puzzle as a share code. Rules must be maximal runs, as in every generated or parsed puzzle
+pub fn encode(puzzle: &Puzzle) -> String {
+    let mut w = BitWriter::default();
+
+    w.push(CODE_VERSION as u32, 8);
+    w.push(puzzle.width as u32, 8);
+    w.push(puzzle.height as u32, 8);
+    w.push(
+        if puzzle.solution.is_some() {
+            HAS_SOLUTION
+        } else {
+            0
+        } as u32,
+        8,
+    );
+
+    let shape = shape_of(puzzle);
+    for &fillable in shape.iter().flatten() {
+        w.push(fillable as u32, 1);
+    }
+
+    for rule in Puzzle::from_shape(&shape).rules {
+        let value = puzzle
+            .rules
+            .iter()
+            .find(|r| (r.x, r.y, r.is_vertical) == (rule.x, rule.y, rule.is_vertical))
+            .map_or(0, |r| r.value);
+
+        w.push(value as u32, 6);
+    }
+
+    if let Some(solution) = &puzzle.solution {
+        for (x, y) in puzzle.fillable_cells() {
+            w.push(solution[y as usize][x as usize] as u32, 4);
+        }
+    }
+
+    let mut bytes = w.bytes;
+    bytes.extend_from_slice(&checksum(&bytes).to_be_bytes());
+
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Decodes a share code made by `encode`
+pub fn decode(code: &str) -> Result<Puzzle, ShareCodeError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(code.trim())
+        .map_err(|_| ShareCodeError::InvalidEncoding)?;
+
+    let Some((body, check)) = bytes.split_last_chunk::<2>() else {
+        return Err(ShareCodeError::ChecksumMismatch);
+    };
+
+    if checksum(body) != u16::from_be_bytes(*check) {
+        return Err(ShareCodeError::ChecksumMismatch);
+    }
+
+    let mut r = BitReader {
+        bytes: body,
+        pos: 0,
+    };
+
+    let version = r.read(8)? as u8;
+    if version != CODE_VERSION {
+        return Err(ShareCodeError::UnsupportedVersion(version));
+    }
+
+    let width = r.read(8)? as u8;
+    let height = r.read(8)? as u8;
+    let flags = r.read(8)? as u8;
+
+    if width == 0 || height == 0 {
+        return Err(ShareCodeError::InvalidPuzzle("empty puzzle".to_string()));
+    }
+
+    let shape = (1..height)
+        .map(|_| (1..width).map(|_| Ok(r.read(1)? == 1)).collect())
+        .collect::<Result<Vec<Vec<bool>>, ShareCodeError>>()?;
+
+    let mut puzzle = Puzzle::from_shape(&shape);
+    // An all-blocked shape loses its width when it has no rows
+    puzzle.width = width;
+
+    for rule in &mut puzzle.rules {
+        rule.value = r.read(6)? as u8;
+
+        if rule.value > 45 {
+            return Err(ShareCodeError::InvalidPuzzle(format!(
+                "clue {} is too large",
+                rule.value
+            )));
+        }
+    }
+
+    if flags & HAS_SOLUTION != 0 {
+        let mut solution = vec![vec![0; width as usize]; height as usize];
+
+        for (x, y) in puzzle.fillable_cells() {
+            let digit = r.read(4)? as u8;
+            if !(1..=9).contains(&digit) {
+                return Err(ShareCodeError::InvalidPuzzle(format!(
+                    "solution digit {} is out of range",
+                    digit
+                )));
+            }
+
+            solution[y as usize][x as usize] = digit;
+        }
+
+        puzzle.solution = Some(solution);
+    }
+
+    Ok(Puzzle::new(
+        puzzle.width,
+        puzzle.height,
+        puzzle.rules,
+        puzzle.solution,
+    ))
+}
+
+/// Fillable cells without the clue row and column, the layout `Puzzle::from_shape` takes
+fn shape_of(puzzle: &Puzzle) -> Vec<Vec<bool>> {
+    (1..puzzle.height)
+        .map(|y| {
+            (1..puzzle.width)
+                .map(|x| puzzle.is_fillable(x, y))
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_gen::{GenConfig, generate_batch};
+    use crate::puzzle::fixtures::solved;
+
+    #[test]
+    fn test_round_trip() {
+        let puzzle = solved();
+        let code = encode(&puzzle);
+
+        assert!(
+            code.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+        assert_eq!(decode(&code), Ok(puzzle.clone()));
+
+        let unsolved = Puzzle::new(puzzle.width, puzzle.height, puzzle.rules.clone(), None);
+        let short = encode(&unsolved);
+        assert!(short.len() < code.len());
+        assert_eq!(decode(&short), Ok(unsolved));
+
+        for puzzle in generate_batch(&GenConfig::new(10, 10), 3).unwrap() {
+            assert_eq!(decode(&encode(&puzzle)), Ok(puzzle));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let code = encode(&solved());
+
+        assert_eq!(decode("not a code!"), Err(ShareCodeError::InvalidEncoding));
+        assert_eq!(
+            decode(&code[..code.len() - 4]),
+            Err(ShareCodeError::ChecksumMismatch)
+        );
+
+        // Change one character
+        let mut typo: Vec<char> = code.chars().collect();
+        typo[5] = if typo[5] == 'A' { 'B' } else { 'A' };
+        let typo: String = typo.into_iter().collect();
+        assert_eq!(decode(&typo), Err(ShareCodeError::ChecksumMismatch));
+
+        let mut bytes = vec![CODE_VERSION + 1, 4, 3, 0];
+        bytes.extend_from_slice(&checksum(&bytes).to_be_bytes());
+        assert_eq!(
+            decode(&URL_SAFE_NO_PAD.encode(bytes)),
+            Err(ShareCodeError::UnsupportedVersion(CODE_VERSION + 1))
+        );
+    }
+}
@@ -0,0 +1,585 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    candidates::{RuleIndex, fitting_combinations, propagate, propagate_cell, restrict_rule},
+    combinations::DigitSet,
+    puzzle::{Puzzle, Rule},
+};
+
+/// Kinds of deduction the solver makes, simplest first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Technique {
+    /// A cell has only one candidate left
+    NakedSingle,
+    /// A digit the rule must contain only fits in one of its cells
+    HiddenSingle,
+    /// Digits that aren't part of any way to make the rule's value are removed
+    RunCombinations,
+    /// Placing a digit would leave some cell with no candidates
+    Contradiction,
+}
+
+impl Display for Technique {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NakedSingle => "Naked single",
+            Self::HiddenSingle => "Hidden single",
+            Self::RunCombinations => "Run combinations",
+            Self::Contradiction => "Contradiction",
+        })
+    }
+}
+
+/// One logical deduction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub technique: Technique,
+    /// Cells the deduction looks at
+    pub cells: Vec<(u8, u8)>,
+    /// Digit placed by this step, as `(x, y, digit)`
+    pub placed: Option<(u8, u8, u8)>,
+    /// Candidates removed from each cell by this step
+    pub eliminated: Vec<((u8, u8), DigitSet)>,
+    pub explanation: String,
+}
+
+/// Logical solver working on the candidates of every cell, one step at a time
+#[derive(Clone, Debug)]
+pub struct Solver<'a> {
+    puzzle: &'a Puzzle,
+    index: RuleIndex,
+    digits: Vec<Vec<u8>>,
+    candidates: Vec<Vec<DigitSet>>,
+}
+
+impl<'a> Solver<'a> {
+    /// Starts from the given digits, 0 being an empty cell, with every other candidate open
+    pub fn new(puzzle: &'a Puzzle, digits: &[Vec<u8>]) -> Self {
+        let mut solver = Self {
+            puzzle,
+            index: RuleIndex::new(puzzle),
+            digits: vec![vec![0; puzzle.width as usize]; puzzle.height as usize],
+            candidates: vec![vec![DigitSet::EMPTY; puzzle.width as usize]; puzzle.height as usize],
+        };
+
+        for (x, y) in puzzle.fillable_cells() {
+            let (ux, uy) = (x as usize, y as usize);
+            let digit = digits.get(uy).and_then(|r| r.get(ux)).copied().unwrap_or(0);
+
+            solver.digits[uy][ux] = digit;
+            solver.candidates[uy][ux] = if digit == 0 {
+                DigitSet::ALL
+            } else {
+                DigitSet::single(digit)
+            };
+        }
+
+        solver
+    }
+
+    /// Same as `new`, but empty cells start with the player's notes instead of every digit
+    pub fn with_notes(puzzle: &'a Puzzle, digits: &[Vec<u8>], notes: &[Vec<DigitSet>]) -> Self {
+        let mut solver = Self::new(puzzle, digits);
+
+        for (x, y) in puzzle.fillable_cells() {
+            let (ux, uy) = (x as usize, y as usize);
+            let note = notes.get(uy).and_then(|r| r.get(ux)).copied();
+
+            if let Some(note) = note.filter(|n| !n.is_empty())
+                && solver.digits[uy][ux] == 0
+            {
+                solver.candidates[uy][ux] = note;
+            }
+        }
+
+        solver
+    }
+
+    pub fn digits(&self) -> &[Vec<u8>] {
+        &self.digits
+    }
+
+    pub fn candidates(&self, x: u8, y: u8) -> DigitSet {
+        self.candidates[y as usize][x as usize]
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.puzzle
+            .fillable_cells()
+            .all(|(x, y)| self.digits[y as usize][x as usize] != 0)
+    }
+
+    /// Finds the simplest available deduction and applies it. `None` if the solver is stuck or done
+    pub fn next_step(&mut self) -> Option<Step> {
+        let step = self
+            .find_naked_single()
+            .or_else(|| self.find_hidden_single())
+            .or_else(|| self.find_run_combinations())
+            .or_else(|| self.find_contradiction())?;
+
+        self.apply(&step);
+
+        Some(step)
+    }
+
+    fn apply(&mut self, step: &Step) {
+        for &((x, y), digits) in &step.eliminated {
+            let cell = &mut self.candidates[y as usize][x as usize];
+            *cell = cell.difference(digits);
+        }
+
+        if let Some((x, y, digit)) = step.placed {
+            self.digits[y as usize][x as usize] = digit;
+            self.candidates[y as usize][x as usize] = DigitSet::single(digit);
+        }
+    }
+
+    fn empty_cells(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        self.puzzle
+            .fillable_cells()
+            .filter(|&(x, y)| self.digits[y as usize][x as usize] == 0)
+    }
+
+    fn rule_sets(&self, rule: &Rule) -> Vec<DigitSet> {
+        rule.cells()
+            .map(|(x, y)| self.candidates[y as usize][x as usize])
+            .collect()
+    }
+
+    /// Step placing `digit`, which also removes it from the candidates of the other cells in both rules
+    fn placement(
+        &self,
+        x: u8,
+        y: u8,
+        digit: u8,
+        technique: Technique,
+        explanation: String,
+    ) -> Step {
+        let (across, down) = self.puzzle.rules_for(x, y);
+        let mut cells = vec![(x, y)];
+        let mut eliminated = Vec::new();
+
+        for rule in [across, down].into_iter().flatten() {
+            for cell in rule.cells().filter(|&c| c != (x, y)) {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+
+                let (cx, cy) = cell;
+                if self.digits[cy as usize][cx as usize] == 0
+                    && self.candidates[cy as usize][cx as usize].contains(digit)
+                {
+                    eliminated.push((cell, DigitSet::single(digit)));
+                }
+            }
+        }
+
+        Step {
+            technique,
+            cells,
+            placed: Some((x, y, digit)),
+            eliminated,
+            explanation,
+        }
+    }
+
+    fn find_naked_single(&self) -> Option<Step> {
+        self.empty_cells().find_map(|(x, y)| {
+            let set = self.candidates(x, y);
+            let digit = set.iter().next().filter(|_| set.len() == 1)?;
+
+            Some(self.placement(
+                x,
+                y,
+                digit,
+                Technique::NakedSingle,
+                format!(
+                    "{} can only be {}, every other digit has been ruled out",
+                    capitalize(describe_cell(x, y)),
+                    digit
+                ),
+            ))
+        })
+    }
+
+    fn find_hidden_single(&self) -> Option<Step> {
+        self.puzzle.rules.iter().find_map(|rule| {
+            let sets = self.rule_sets(rule);
+            let required = required_digits(rule.value, &sets);
+
+            required.iter().find_map(|digit| {
+                let mut fits = rule.cells().zip(&sets).filter(|(_, s)| s.contains(digit));
+                let ((x, y), _) = fits.next()?;
+
+                if fits.next().is_some() || self.digits[y as usize][x as usize] != 0 {
+                    return None;
+                }
+
+                Some(self.placement(
+                    x,
+                    y,
+                    digit,
+                    Technique::HiddenSingle,
+                    format!(
+                        "{} must contain a {}, and {} is the only cell where it fits",
+                        capitalize(describe_rule(rule)),
+                        digit,
+                        describe_cell(x, y)
+                    ),
+                ))
+            })
+        })
+    }
+
+    fn find_run_combinations(&self) -> Option<Step> {
+        self.puzzle.rules.iter().find_map(|rule| {
+            let sets = self.rule_sets(rule);
+            let restricted = restrict_rule(rule.value, &sets);
+
+            let eliminated: Vec<((u8, u8), DigitSet)> = rule
+                .cells()
+                .zip(sets.iter().zip(&restricted))
+                .filter(|(_, (old, new))| old != new)
+                .map(|(cell, (old, new))| (cell, old.difference(*new)))
+                .collect();
+
+            if eliminated.is_empty() {
+                return None;
+            }
+
+            let possible = restricted
+                .iter()
+                .fold(DigitSet::EMPTY, |acc, &s| acc.union(s));
+
+            let explanation = if rule.value == 0 {
+                format!(
+                    "{} can't repeat a digit that's already placed in it",
+                    capitalize(describe_rule(rule))
+                )
+            } else {
+                let options = fitting_combinations(rule.value, &sets);
+
+                if options.len() <= 3 {
+                    format!(
+                        "{} can only be made from {}, so its cells are limited to {}",
+                        capitalize(describe_rule(rule)),
+                        options
+                            .iter()
+                            .map(|c| join_digits(*c, "+"))
+                            .collect::<Vec<_>>()
+                            .join(" or "),
+                        list_digits(possible)
+                    )
+                } else {
+                    format!(
+                        "With the digits it can still use, {} limits its cells to {}",
+                        describe_rule(rule),
+                        list_digits(possible)
+                    )
+                }
+            };
+
+            Some(Step {
+                technique: Technique::RunCombinations,
+                cells: rule.cells().collect(),
+                placed: None,
+                eliminated,
+                explanation,
+            })
+        })
+    }
+
+    fn find_contradiction(&self) -> Option<Step> {
+        // Only cells down to two candidates, the ones a player would try. Trying every digit of every cell takes
+        // seconds on a large board once the solver is stuck
+        let cells = self
+            .empty_cells()
+            .filter(|&(x, y)| self.candidates(x, y).len() == 2);
+
+        cells.into_iter().find_map(|(x, y)| {
+            self.candidates(x, y).iter().find_map(|digit| {
+                let mut trial = self.candidates.clone();
+                trial[y as usize][x as usize] = DigitSet::single(digit);
+
+                if propagate_cell(self.puzzle, &self.index, &mut trial, x, y) {
+                    return None;
+                }
+
+                Some(Step {
+                    technique: Technique::Contradiction,
+                    cells: vec![(x, y)],
+                    placed: None,
+                    eliminated: vec![((x, y), DigitSet::single(digit))],
+                    explanation: format!(
+                        "If {} were {}, following the rules from there would leave a cell with no possible digits",
+                        describe_cell(x, y),
+                        digit
+                    ),
+                })
+            })
+        })
+    }
+}
+
+/// Digits in every combination of the rule that still fits its cells
+fn required_digits(value: u8, sets: &[DigitSet]) -> DigitSet {
+    if value == 0 {
+        return DigitSet::EMPTY;
+    }
+
+    fitting_combinations(value, sets)
+        .into_iter()
+        .reduce(DigitSet::intersection)
+        .unwrap_or(DigitSet::EMPTY)
+}
+
+fn describe_cell(x: u8, y: u8) -> String {
+    format!("the cell at row {}, column {}", y, x)
+}
+
+fn describe_rule(rule: &Rule) -> String {
+    let (first, last) = (rule.cells().next(), rule.cells().last());
+    let ((fx, fy), (lx, ly)) = (first.unwrap_or_default(), last.unwrap_or_default());
+
+    if rule.is_vertical {
+        format!(
+            "the {} down in column {} (rows {}-{})",
+            rule.value, fx, fy, ly
+        )
+    } else {
+        format!(
+            "the {} across in row {} (columns {}-{})",
+            rule.value, fy, fx, lx
+        )
+    }
+}
+
+fn capitalize(text: String) -> String {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
+
+fn join_digits(set: DigitSet, sep: &str) -> String {
+    set.iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+/// "1, 2 or 4"
+fn list_digits(set: DigitSet) -> String {
+    let digits: Vec<String> = set.iter().map(|d| d.to_string()).collect();
+
+    match digits.split_last() {
+        None => "nothing".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+/// A hint for the player: the next deduction, and the eliminations leading up to it if the player has no notes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    pub step: Step,
+    /// Earlier eliminations on the cells the step looks at, which the player needs to follow it
+    pub supporting: Vec<Step>,
+}
+
+/// Finds the next logical deduction from the player's digits, 0 being an empty cell.
+///
+/// With `notes` the hint can be an elimination for the player to apply to their notes. Without them, eliminations are
+/// worked out behind the scenes and the hint is the first digit that can be placed, with the eliminations it relies on.
+/// Returns `None` if the digits are already wrong or no deduction is available
+pub fn next_hint(
+    puzzle: &Puzzle,
+    digits: &[Vec<u8>],
+    notes: Option<&[Vec<DigitSet>]>,
+) -> Option<Hint> {
+    let mut solver = match notes {
+        Some(notes) => Solver::with_notes(puzzle, digits, notes),
+        None => Solver::new(puzzle, digits),
+    };
+
+    // Placed digits that break a rule can't lead anywhere useful
+    let mut check = solver.candidates.clone();
+    if !propagate(puzzle, &solver.index, &mut check) {
+        return None;
+    }
+
+    if notes.is_some() {
+        return solver.next_step().map(|step| Hint {
+            step,
+            supporting: Vec::new(),
+        });
+    }
+
+    let mut eliminations: Vec<Step> = Vec::new();
+
+    while let Some(step) = solver.next_step() {
+        if step.placed.is_none() {
+            eliminations.push(step);
+            continue;
+        }
+
+        let supporting = eliminations
+            .into_iter()
+            .filter(|e| e.eliminated.iter().any(|(c, _)| step.cells.contains(c)))
+            .collect();
+
+        return Some(Hint { step, supporting });
+    }
+
+    None
+}
+
+/// Hardest technique the solver needs to solve the puzzle from an empty board. `None` if it gets stuck, meaning the
+/// puzzle can't be solved without guessing
+pub fn grade(puzzle: &Puzzle) -> Option<Technique> {
+    let mut solver = Solver::new(puzzle, &[]);
+    let mut hardest = Technique::NakedSingle;
+
+    while let Some(step) = solver.next_step() {
+        hardest = hardest.max(step.technique);
+    }
+
+    solver.is_solved().then_some(hardest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::grid_gen::{GenConfig, generate_puzzle};
+    use crate::puzzle::fixtures::{ambiguous, unsolved};
+
+    #[test]
+    fn test_solver_steps() {
+        let puzzle = unsolved();
+        let mut solver = Solver::new(&puzzle, &[]);
+
+        let first = solver.next_step().unwrap();
+        assert_eq!(first.technique, Technique::RunCombinations);
+        assert_eq!(first.cells, vec![(1, 1), (1, 2)]);
+        assert_eq!(
+            first.explanation,
+            "The 17 down in column 1 (rows 1-2) can only be made from 8+9, so its cells are limited to 8 or 9"
+        );
+
+        let mut steps = vec![first];
+        while let Some(step) = solver.next_step() {
+            steps.push(step);
+        }
+
+        assert!(solver.is_solved());
+        assert_eq!(
+            solver.digits(),
+            [vec![0, 0, 0, 0], vec![0, 9, 7, 0], vec![0, 8, 4, 0]]
+        );
+        assert_eq!(steps.iter().filter(|s| s.placed.is_some()).count(), 4);
+    }
+
+    #[test]
+    fn test_next_hint() {
+        let puzzle = unsolved();
+        let empty = vec![vec![0; 4]; 3];
+
+        // Without notes the hint is a placement, with the eliminations behind it
+        let hint = next_hint(&puzzle, &empty, None).unwrap();
+        assert_eq!(hint.step.placed, Some((2, 1, 7)));
+        assert_eq!(hint.step.technique, Technique::HiddenSingle);
+        assert!(!hint.supporting.is_empty());
+
+        // With notes already narrowed down, the hint is what to do next
+        let mut notes = vec![vec![DigitSet::EMPTY; 4]; 3];
+        notes[1][1] = DigitSet::single(9);
+        let hint = next_hint(&puzzle, &empty, Some(&notes)).unwrap();
+        assert_eq!(hint.step.placed, Some((1, 1, 9)));
+        assert!(hint.supporting.is_empty());
+
+        // Wrong digits get no hint
+        let wrong = vec![vec![0; 4], vec![0, 8, 0, 0], vec![0; 4]];
+        assert_eq!(next_hint(&puzzle, &wrong, None), None);
+    }
+
+    #[test]
+    fn test_generated_board() {
+        let puzzle = generate_puzzle(&GenConfig::new(16, 16).with_seed(3)).unwrap();
+        let solution = puzzle.solution.clone().unwrap();
+        let empty = vec![vec![0; puzzle.width as usize]; puzzle.height as usize];
+        let start = Instant::now();
+
+        // The solution is the only one, so every digit the solver places is part of it
+        let mut solver = Solver::new(&puzzle, &[]);
+        let mut steps = 0;
+        while let Some(step) = solver.next_step() {
+            if let Some((x, y, digit)) = step.placed {
+                assert_eq!(digit, solution[y as usize][x as usize]);
+            }
+            steps += 1;
+        }
+        assert!(steps > 0);
+
+        // Hints place correct digits, from an empty board and from part of the way through
+        let half = solver
+            .digits()
+            .iter()
+            .flatten()
+            .filter(|&&d| d != 0)
+            .count()
+            / 2;
+        let mut partial = empty.clone();
+        for (x, y) in puzzle.fillable_cells().take(half) {
+            partial[y as usize][x as usize] = solution[y as usize][x as usize];
+        }
+        for digits in [&empty, &partial] {
+            let hint = next_hint(&puzzle, digits, None).unwrap();
+            let (x, y, digit) = hint.step.placed.unwrap();
+            assert_eq!(digits[y as usize][x as usize], 0);
+            assert_eq!(digit, solution[y as usize][x as usize]);
+        }
+
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "took {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_grade() {
+        // Both rules through the top left cell only allow a 9 there, and the rest follows
+        let puzzle = unsolved();
+        assert_eq!(grade(&puzzle), Some(Technique::RunCombinations));
+
+        assert_eq!(grade(&ambiguous()), None);
+    }
+
+    #[test]
+    fn test_hidden_single() {
+        let puzzle = unsolved();
+
+        // The 17 down needs a 9, and only its top cell still allows one
+        let mut notes = vec![vec![DigitSet::EMPTY; 4]; 3];
+        notes[1][1] = DigitSet::from_iter([1, 8, 9]);
+        notes[2][1] = DigitSet::from_iter([2, 8]);
+
+        let mut solver = Solver::with_notes(&puzzle, &[], &notes);
+        let step = solver.next_step().unwrap();
+
+        assert_eq!(step.technique, Technique::HiddenSingle);
+        assert_eq!(step.placed, Some((1, 1, 9)));
+        assert_eq!(
+            step.explanation,
+            "The 17 down in column 1 (rows 1-2) must contain a 9, and the cell at row 1, column 1 is the only cell where it fits"
+        );
+        assert_eq!(list_digits(DigitSet::from_iter([1, 2, 4])), "1, 2 or 4");
+    }
+}
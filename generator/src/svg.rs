@@ -0,0 +1,149 @@
+use std::fmt::Write;
+
+use crate::{
+    puzzle::Puzzle,
+    render::{BASE_CELL_SIZE, Canvas, Color, draw_puzzle},
+};
+
+/// What to draw in the fillable cells
+#[derive(Clone, Copy, Debug, Default)]
+pub enum SvgDigits<'a> {
+    /// Empty puzzle, for printing
+    #[default]
+    None,
+    /// The puzzle's solution, if it has one
+    Solution,
+    /// Player progress, one digit per cell with 0 for empty cells
+    Progress(&'a [Vec<u8>]),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SvgOptions<'a> {
+    /// Size of each cell in pixels
+    pub cell_size: u32,
+    pub digits: SvgDigits<'a>,
+}
+
+impl Default for SvgOptions<'_> {
+    fn default() -> Self {
+        Self {
+            cell_size: BASE_CELL_SIZE as u32,
+            digits: SvgDigits::None,
+        }
+    }
+}
+
+struct SvgCanvas {
+    svg: String,
+}
+
+impl SvgCanvas {
+    fn color(color: Color) -> &'static str {
+        match color {
+            Color::Black => "black",
+            Color::White => "white",
+        }
+    }
+}
+
+// Writing to a String can't fail
+impl Canvas for SvgCanvas {
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, fill: Color) {
+        let _ = writeln!(
+            self.svg,
+            r#"<rect x="{x}" y="{y}" width="{width}" height="{height}" fill="{}" stroke="black"/>"#,
+            Self::color(fill)
+        );
+    }
+
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: Color) {
+        let _ = writeln!(
+            self.svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{width}"/>"#,
+            from.0,
+            from.1,
+            to.0,
+            to.1,
+            Self::color(color)
+        );
+    }
+
+    fn text(&mut self, x: f32, y: f32, size: f32, color: Color, text: &str) {
+        let _ = writeln!(
+            self.svg,
+            r#"<text x="{x}" y="{y}" font-size="{size}" fill="{}" text-anchor="middle" dominant-baseline="central">{text}</text>"#,
+            Self::color(color)
+        );
+    }
+}
+
+/// Renders the puzzle as a standalone SVG document
+pub fn render_svg(puzzle: &Puzzle, options: &SvgOptions) -> String {
+    let size = options.cell_size as f32;
+    let (width, height) = (puzzle.width as f32 * size, puzzle.height as f32 * size);
+
+    let digits = match options.digits {
+        SvgDigits::None => None,
+        SvgDigits::Solution => puzzle.solution.as_deref(),
+        SvgDigits::Progress(digits) => Some(digits),
+    };
+
+    let mut canvas = SvgCanvas {
+        svg: format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif">"#,
+                "\n",
+                r#"<rect width="{0}" height="{1}" fill="white"/>"#,
+                "\n"
+            ),
+            width, height
+        ),
+    };
+
+    draw_puzzle(&mut canvas, puzzle, digits, (0.0, 0.0), size);
+
+    canvas.svg.push_str("</svg>\n");
+
+    canvas.svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::fixtures::solved;
+
+    #[test]
+    fn test_render_svg() {
+        let puzzle = solved();
+
+        let blank = render_svg(&puzzle, &SvgOptions::default());
+
+        assert!(blank.starts_with("<svg") && blank.ends_with("</svg>\n"));
+        assert!(blank.contains(r#"width="128" height="96""#));
+        // Single rules use the one-rule offsets from the game
+        assert!(blank.contains(r#"<text x="49" y="22""#));
+        assert!(blank.contains(">16</text>"));
+        assert!(!blank.contains(">9</text>"));
+
+        let solved = render_svg(
+            &puzzle,
+            &SvgOptions {
+                cell_size: 64,
+                digits: SvgDigits::Solution,
+            },
+        );
+        assert!(solved.contains(r#"<text x="96" y="96" font-size="38.4""#));
+        assert!(solved.contains(">9</text>"));
+
+        let progress = vec![vec![0; 4], vec![0, 5, 0, 0], vec![0; 4]];
+        let partial = render_svg(
+            &puzzle,
+            &SvgOptions {
+                digits: SvgDigits::Progress(&progress),
+                ..Default::default()
+            },
+        );
+        assert!(partial.contains(">5</text>"));
+        assert!(!partial.contains(">9</text>"));
+    }
+}
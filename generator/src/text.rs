@@ -0,0 +1,370 @@
+//! Plain text formats for shapes and puzzles, for logs, bug reports and test fixtures.
+//!
+//! Shapes use the same characters the generator prints, one line per row:
+//!
+//! | Char        | Cell         |
+//! |-------------|--------------|
+//! | `O` `C` `F` | Fillable     |
+//! | `,` `_`     | Not fillable |
+//!
+//! Puzzles are whitespace-separated tokens, one line per row, including the clue row and column:
+//!
+//! | Token   | Cell                                                       |
+//! |---------|------------------------------------------------------------|
+//! | `#`     | Clue cell with no rules                                    |
+//! | `17\16` | Clue cell, down value first. Either side may be left empty |
+//! | `1`-`9` | Fillable cell with its solution digit                      |
+//! | `.`     | Fillable cell with an unknown digit                        |
+//!
+//! ```text
+//! #    17\  11\
+//! \16  9    7
+//! \12  8    4
+//! ```
+//!
+//! Either every fillable cell has a digit or none do. Blank lines are ignored.
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    error::ParseError,
+    puzzle::{Puzzle, Rule},
+};
+
+/// Parses a shape printed by the generator, `true` marking fillable cells
+pub fn parse_shape(s: &str) -> Result<Vec<Vec<bool>>, ParseError> {
+    let mut rows: Vec<Vec<bool>> = Vec::new();
+
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let row = line
+            .chars()
+            .map(|c| match c {
+                'O' | 'C' | 'F' => Ok(true),
+                ',' | '_' => Ok(false),
+                _ => Err(ParseError::new(i + 1, format!("unknown cell '{}'", c))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if rows.first().is_some_and(|r| r.len() != row.len()) {
+            return Err(ParseError::new(i + 1, "rows must all be the same length"));
+        }
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Formats a shape so `parse_shape` can read it back
+pub fn format_shape(shape: &[Vec<bool>]) -> String {
+    shape
+        .iter()
+        .map(|r| {
+            r.iter()
+                .map(|&c| if c { 'O' } else { ',' })
+                .collect::<String>()
+                + "\n"
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Clue(Option<u8>, Option<u8>),
+    Fillable(Option<u8>),
+}
+
+impl Token {
+    fn parse(s: &str, line: usize) -> Result<Self, ParseError> {
+        let value = |v: &str| -> Result<Option<u8>, ParseError> {
+            if v.is_empty() {
+                return Ok(None);
+            }
+
+            match v.parse::<u8>() {
+                Ok(n) if n <= 45 => Ok(Some(n)),
+                _ => Err(ParseError::new(line, format!("invalid rule value '{}'", v))),
+            }
+        };
+
+        match s {
+            "#" => Ok(Self::Clue(None, None)),
+            "." => Ok(Self::Fillable(None)),
+            _ => {
+                if let Some((down, across)) = s.split_once('\\') {
+                    Ok(Self::Clue(value(down)?, value(across)?))
+                } else if let Ok(digit @ 1..=9) = s.parse::<u8>() {
+                    Ok(Self::Fillable(Some(digit)))
+                } else {
+                    Err(ParseError::new(line, format!("unknown cell '{}'", s)))
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells: Vec<Vec<Token>> = Vec::new();
+        // Source line of each row, for error messages
+        let mut lines: Vec<usize> = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let row = line
+                .split_whitespace()
+                .map(|t| Token::parse(t, i + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if row.is_empty() {
+                continue;
+            }
+            if cells.first().is_some_and(|r| r.len() != row.len()) {
+                return Err(ParseError::new(i + 1, "rows must all be the same length"));
+            }
+
+            cells.push(row);
+            lines.push(i + 1);
+        }
+
+        let height = cells.len();
+        let width = cells.first().map_or(0, |r| r.len());
+
+        if width > u8::MAX as usize || height > u8::MAX as usize {
+            return Err(ParseError::new(0, "puzzle is too large"));
+        }
+
+        let is_fillable = |x: usize, y: usize| matches!(cells[y][x], Token::Fillable(_));
+
+        let mut rules = Vec::new();
+
+        for (y, row) in cells.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                match cell {
+                    Token::Clue(down, across) => {
+                        let down_len = (y + 1..height).take_while(|&ny| is_fillable(x, ny)).count();
+                        let across_len =
+                            (x + 1..width).take_while(|&nx| is_fillable(nx, y)).count();
+
+                        for (value, len, is_vertical) in
+                            [(down, down_len, true), (across, across_len, false)]
+                        {
+                            match (value, len) {
+                                (Some(value), 0) => {
+                                    return Err(ParseError::new(
+                                        lines[y],
+                                        format!("rule with value {} has no cells", value),
+                                    ));
+                                }
+                                (Some(value), len) => rules.push(Rule::new(
+                                    x as u8,
+                                    y as u8,
+                                    len as u8,
+                                    value,
+                                    is_vertical,
+                                )),
+                                (None, _) => {}
+                            }
+                        }
+                    }
+                    Token::Fillable(_) => {
+                        // Every fillable cell needs a rule in both directions
+                        let left = (0..x).rev().find(|&nx| !is_fillable(nx, y));
+                        let up = (0..y).rev().find(|&ny| !is_fillable(x, ny));
+
+                        let has_across =
+                            left.is_some_and(|nx| matches!(cells[y][nx], Token::Clue(_, Some(_))));
+                        let has_down =
+                            up.is_some_and(|ny| matches!(cells[ny][x], Token::Clue(Some(_), _)));
+
+                        if !has_across || !has_down {
+                            return Err(ParseError::new(
+                                lines[y],
+                                format!("cell in column {} is missing a rule", x + 1),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let digits: Vec<Option<u8>> = cells
+            .iter()
+            .flatten()
+            .filter_map(|c| match c {
+                Token::Fillable(d) => Some(*d),
+                Token::Clue(..) => None,
+            })
+            .collect();
+
+        let solution = if digits.iter().all(Option::is_some) && !digits.is_empty() {
+            Some(
+                cells
+                    .iter()
+                    .map(|r| {
+                        r.iter()
+                            .map(|c| match c {
+                                Token::Fillable(d) => d.unwrap_or(0),
+                                Token::Clue(..) => 0,
+                            })
+                            .collect()
+                    })
+                    .collect::<Vec<Vec<u8>>>(),
+            )
+        } else if digits.iter().all(Option::is_none) {
+            None
+        } else {
+            return Err(ParseError::new(
+                0,
+                "either every cell or no cells must have digits",
+            ));
+        };
+
+        let puzzle = Puzzle::new(width as u8, height as u8, rules, solution);
+
+        if let Some(solution) = &puzzle.solution {
+            for rule in &puzzle.rules {
+                let digits: Vec<u8> = rule
+                    .cells()
+                    .map(|(x, y)| solution[y as usize][x as usize])
+                    .collect();
+
+                let repeats = (1..digits.len()).any(|i| digits[..i].contains(&digits[i]));
+
+                if repeats || digits.iter().map(|&d| d as u32).sum::<u32>() != rule.value as u32 {
+                    return Err(ParseError::new(
+                        lines[rule.y as usize],
+                        format!(
+                            "digits don't solve the rule in column {}",
+                            rule.x as usize + 1
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(puzzle)
+    }
+}
+
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tokens: Vec<Vec<String>> = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        if self.is_fillable(x, y) {
+                            match &self.solution {
+                                Some(solution) => solution[y as usize][x as usize].to_string(),
+                                None => ".".to_string(),
+                            }
+                        } else {
+                            match self.clues_at(x, y) {
+                                (None, None) => "#".to_string(),
+                                (down, across) => format!(
+                                    "{}\\{}",
+                                    down.map(|v| v.to_string()).unwrap_or_default(),
+                                    across.map(|v| v.to_string()).unwrap_or_default()
+                                ),
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Pad every column to the same width so the grid lines up
+        let col_width = tokens.iter().flatten().map(|t| t.len()).max().unwrap_or(0);
+
+        for row in tokens {
+            let line = row
+                .iter()
+                .map(|t| format!("{:<col_width$}", t))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::fixtures::SOLVED;
+
+    #[test]
+    fn test_parse_shape() {
+        let shape = parse_shape("OOC,\n_FOO\n").unwrap();
+
+        assert_eq!(
+            shape,
+            vec![vec![true, true, true, false], vec![false, true, true, true]]
+        );
+        assert_eq!(parse_shape(&format_shape(&shape)).unwrap(), shape);
+        assert_eq!(parse_shape("OO\nO").unwrap_err().line, 2);
+        assert_eq!(parse_shape("OX").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_parse_puzzle() {
+        // A clue with no run after it
+        let err = format!("{}#    #    1\\   #", SOLVED)
+            .parse::<Puzzle>()
+            .unwrap_err();
+        assert_eq!(err.line, 5);
+
+        let puzzle: Puzzle = SOLVED.parse().unwrap();
+
+        assert_eq!(
+            puzzle.rules,
+            vec![
+                Rule::new_vert(1, 0, 2, 17),
+                Rule::new_vert(2, 0, 2, 11),
+                Rule::new_horiz(0, 1, 2, 16),
+                Rule::new_horiz(0, 2, 2, 12),
+            ]
+        );
+        assert_eq!(puzzle.solution.as_ref().unwrap()[2][2], 4);
+        assert_eq!(puzzle.to_string().parse::<Puzzle>().unwrap(), puzzle);
+    }
+
+    #[test]
+    fn test_parse_puzzle_errors() {
+        // Wrong sum
+        assert!("# 3\\ \n\\3 2".parse::<Puzzle>().is_err());
+        // Missing across rule
+        assert!("# 3\\ \n# .".parse::<Puzzle>().is_err());
+        // Some digits but not all
+        assert!("# 3\\ 4\\\n\\7 . 4".parse::<Puzzle>().is_err());
+
+        let unsolved: Puzzle = "# 3\\ 4\\\n\\7 . .".parse().unwrap();
+        assert_eq!(unsolved.solution, None);
+        assert_eq!(unsolved.to_string(), "#  3\\ 4\\\n\\7 .  .\n");
+    }
+
+    #[test]
+    fn test_shape_round_trip() {
+        let shape = parse_shape(
+            "
+            OO,OOO
+            OOOOOO
+            ,OO,OO
+            ",
+        )
+        .unwrap();
+
+        let puzzle = Puzzle::from_shape(&shape);
+        let reparsed: Puzzle = puzzle.to_string().parse().unwrap();
+
+        assert_eq!(reparsed, puzzle);
+    }
+}
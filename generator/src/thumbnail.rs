@@ -0,0 +1,115 @@
+use crate::puzzle::Puzzle;
+
+/// Tile size used by the game, `Const.PX_PER_TILE`
+pub const PX_PER_TILE: u32 = 32;
+
+const BLACK: u8 = 0x00;
+const WHITE: u8 = 0xff;
+const GRID_LINE: u8 = 0xa0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ThumbnailOptions {
+    pub px_per_cell: u32,
+    /// Outline fillable cells so neighbouring ones can be told apart. Skipped below 4 pixels per cell
+    pub grid_lines: bool,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            px_per_cell: PX_PER_TILE,
+            grid_lines: true,
+        }
+    }
+}
+
+/// Renders the layout of the puzzle as a grayscale PNG: black clue cells and white fillable cells, no digits.
+/// Fails for an empty puzzle, PNGs can't be 0 pixels wide or tall
+pub fn render_thumbnail(
+    puzzle: &Puzzle,
+    options: &ThumbnailOptions,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let px = options.px_per_cell.max(1) as usize;
+    let (width, height) = (puzzle.width as usize * px, puzzle.height as usize * px);
+    let grid_lines = options.grid_lines && px >= 4;
+
+    let mut pixels = vec![BLACK; width * height];
+
+    for (x, y) in puzzle.fillable_cells() {
+        let (left, top) = (x as usize * px, y as usize * px);
+
+        for row in top..top + px {
+            for col in left..left + px {
+                let on_edge =
+                    row == top || col == left || row == top + px - 1 || col == left + px - 1;
+
+                pixels[row * width + col] = if grid_lines && on_edge {
+                    GRID_LINE
+                } else {
+                    WHITE
+                };
+            }
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut w| w.write_image_data(&pixels))?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::parse_shape;
+
+    fn decode(bytes: &[u8]) -> (u32, u32, Vec<u8>) {
+        let mut reader = png::Decoder::new(bytes).read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+
+        (info.width, info.height, buf)
+    }
+
+    #[test]
+    fn test_render_thumbnail() {
+        let puzzle = Puzzle::from_shape(&parse_shape("OO,\nOOO").unwrap());
+
+        let (w, h, pixels) =
+            decode(&render_thumbnail(&puzzle, &ThumbnailOptions::default()).unwrap());
+        assert_eq!((w, h), (4 * 32, 3 * 32));
+        // Middle of the clue corner, the edge and middle of the first fillable cell
+        assert_eq!(pixels[16 * 128 + 16], BLACK);
+        assert_eq!(pixels[32 * 128 + 32], GRID_LINE);
+        assert_eq!(pixels[48 * 128 + 48], WHITE);
+
+        let (w, h, pixels) = decode(
+            &render_thumbnail(
+                &puzzle,
+                &ThumbnailOptions {
+                    px_per_cell: 1,
+                    grid_lines: true,
+                },
+            )
+            .unwrap(),
+        );
+        assert_eq!((w, h), (4, 3));
+        assert_eq!(
+            pixels,
+            [
+                BLACK, BLACK, BLACK, BLACK, //
+                BLACK, WHITE, WHITE, BLACK, //
+                BLACK, WHITE, WHITE, WHITE,
+            ]
+        );
+
+        let empty: Puzzle = "".parse().unwrap();
+        assert!(render_thumbnail(&empty, &ThumbnailOptions::default()).is_err());
+    }
+}
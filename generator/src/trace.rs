@@ -0,0 +1,364 @@
+//! Records every step of a logical solve, for walkthroughs in the game and for comparing solver versions.
+//!
+//! Traces are JSON with the puzzle's rules in the same shape as `Rule` in the game, so a trace can be replayed without
+//! the original puzzle:
+//!
+//! ```json
+//! {
+//!   "solver_version": 1,
+//!   "width": 3,
+//!   "height": 3,
+//!   "rules": [{ "x": 1, "y": 0, "is_vertical": true, "num_cells": 2, "value": 17 }],
+//!   "start": [[0, 0, 0], [0, 0, 0], [0, 0, 0]],
+//!   "steps": [{
+//!     "technique": "naked_single",
+//!     "cells": [{ "x": 1, "y": 1 }],
+//!     "placed": { "x": 1, "y": 1, "digit": 9 },
+//!     "eliminated": [{ "x": 1, "y": 2, "digits": [9] }],
+//!     "explanation": "..."
+//!   }],
+//!   "solved": true
+//! }
+//! ```
+//!
+//! `placed` is `null` for steps that only remove candidates.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combinations::{DigitSet, max_sum, min_sum},
+    error::ParseError,
+    puzzle::{Puzzle, Rule},
+    solver::{Solver, Step, Technique},
+};
+
+/// Bumped whenever the solver's techniques or their order change, so traces from different versions can be told apart
+pub const SOLVER_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceCell {
+    pub x: u8,
+    pub y: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Placement {
+    pub x: u8,
+    pub y: u8,
+    pub digit: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Elimination {
+    pub x: u8,
+    pub y: u8,
+    /// Candidates removed from the cell, ascending
+    pub digits: Vec<u8>,
+}
+
+/// One step of the trace, the serializable form of a solver `Step`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub technique: Technique,
+    pub cells: Vec<TraceCell>,
+    pub placed: Option<Placement>,
+    pub eliminated: Vec<Elimination>,
+    pub explanation: String,
+}
+
+impl TraceStep {
+    /// Whether both steps make the same deduction, regardless of how it's explained
+    pub fn same_deduction(&self, other: &Self) -> bool {
+        self.technique == other.technique
+            && self.cells == other.cells
+            && self.placed == other.placed
+            && self.eliminated == other.eliminated
+    }
+}
+
+impl From<&Step> for TraceStep {
+    fn from(step: &Step) -> Self {
+        Self {
+            technique: step.technique,
+            cells: step
+                .cells
+                .iter()
+                .map(|&(x, y)| TraceCell { x, y })
+                .collect(),
+            placed: step.placed.map(|(x, y, digit)| Placement { x, y, digit }),
+            eliminated: step
+                .eliminated
+                .iter()
+                .map(|&((x, y), digits)| Elimination {
+                    x,
+                    y,
+                    digits: digits.iter().collect(),
+                })
+                .collect(),
+            explanation: step.explanation.clone(),
+        }
+    }
+}
+
+/// A whole logical solve, from the starting digits until the solver is done or stuck
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trace {
+    pub solver_version: u32,
+    pub width: u8,
+    pub height: u8,
+    pub rules: Vec<Rule>,
+    /// Digits placed before the first step, 0 for empty cells
+    pub start: Vec<Vec<u8>>,
+    pub steps: Vec<TraceStep>,
+    /// Whether the steps fill in every cell. `false` means the solver got stuck
+    pub solved: bool,
+}
+
+impl Trace {
+    pub fn to_json(&self) -> String {
+        // Only plain structs with string keys, which can't fail to serialize
+        serde_json::to_string_pretty(self).expect("Unable to serialize trace")
+    }
+
+    /// Reads a trace, checking that every rule, digit and step is inside the puzzle so it can be replayed
+    pub fn from_json(json: &str) -> Result<Self, ParseError> {
+        // serde_json's message already says where the error is
+        let trace: Self =
+            serde_json::from_str(json).map_err(|e| ParseError::new(0, e.to_string()))?;
+
+        trace
+            .check()
+            .map_err(|message| ParseError::new(0, message))?;
+
+        Ok(trace)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let (width, height) = (self.width, self.height);
+        let in_bounds = |x: u8, y: u8| x < width && y < height;
+        let is_digit = |d: &u8| (1..=9).contains(d);
+
+        for rule in &self.rules {
+            if !rule.fits(width, height) {
+                return Err(format!(
+                    "rule at {},{} goes outside the {}x{} puzzle",
+                    rule.x, rule.y, width, height
+                ));
+            }
+
+            let reachable = min_sum(rule.num_cells)
+                .zip(max_sum(rule.num_cells))
+                .is_some_and(|(min, max)| rule.value == 0 || (min..=max).contains(&rule.value));
+
+            if !reachable {
+                return Err(format!(
+                    "rule at {},{} can't have {} cells adding up to {}",
+                    rule.x, rule.y, rule.num_cells, rule.value
+                ));
+            }
+        }
+
+        let start_fits = self.start.len() == height as usize
+            && self
+                .start
+                .iter()
+                .all(|r| r.len() == width as usize && r.iter().all(|&d| d <= 9));
+
+        if !start_fits {
+            return Err("start digits don't match the puzzle size".to_string());
+        }
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let cells_fit = step.cells.iter().all(|c| in_bounds(c.x, c.y))
+                && step
+                    .placed
+                    .is_none_or(|p| in_bounds(p.x, p.y) && is_digit(&p.digit))
+                && step
+                    .eliminated
+                    .iter()
+                    .all(|e| in_bounds(e.x, e.y) && e.digits.iter().all(is_digit));
+
+            if !cells_fit {
+                return Err(format!("step {} goes outside the puzzle", i + 1));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Digits after the first `count` steps
+    pub fn digits_after(&self, count: usize) -> Vec<Vec<u8>> {
+        let mut digits = self.start.clone();
+
+        for placed in self.steps.iter().take(count).filter_map(|s| s.placed) {
+            digits[placed.y as usize][placed.x as usize] = placed.digit;
+        }
+
+        digits
+    }
+
+    /// Candidates of every cell after the first `count` steps, empty for clue cells and placed digits
+    pub fn candidates_after(&self, count: usize) -> Vec<Vec<DigitSet>> {
+        let puzzle = Puzzle::new(self.width, self.height, self.rules.clone(), None);
+        let mut candidates = vec![vec![DigitSet::EMPTY; self.width as usize]; self.height as usize];
+
+        for (x, y) in puzzle.fillable_cells() {
+            if self.start[y as usize][x as usize] == 0 {
+                candidates[y as usize][x as usize] = DigitSet::ALL;
+            }
+        }
+
+        for step in self.steps.iter().take(count) {
+            for e in &step.eliminated {
+                let cell = &mut candidates[e.y as usize][e.x as usize];
+                *cell = cell.difference(e.digits.iter().copied().collect());
+            }
+
+            if let Some(placed) = step.placed {
+                candidates[placed.y as usize][placed.x as usize] = DigitSet::EMPTY;
+            }
+        }
+
+        candidates
+    }
+
+    /// Index of the first step where the two traces make different deductions, `None` if they're the same
+    pub fn first_difference(&self, other: &Self) -> Option<usize> {
+        let common = self.steps.len().min(other.steps.len());
+
+        (0..common)
+            .find(|&i| !self.steps[i].same_deduction(&other.steps[i]))
+            .or((self.steps.len() != other.steps.len()).then_some(common))
+    }
+}
+
+/// Solves the puzzle logically from `digits`, 0 being an empty cell, recording every step
+pub fn trace_solve(puzzle: &Puzzle, digits: &[Vec<u8>]) -> Trace {
+    let mut solver = Solver::new(puzzle, digits);
+    let start = solver.digits().to_vec();
+
+    let mut steps = Vec::new();
+    while let Some(step) = solver.next_step() {
+        steps.push(TraceStep::from(&step));
+    }
+
+    Trace {
+        solver_version: SOLVER_VERSION,
+        width: puzzle.width,
+        height: puzzle.height,
+        rules: puzzle.rules.clone(),
+        start,
+        steps,
+        solved: solver.is_solved(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        grid_gen::{GenConfig, generate_puzzle},
+        puzzle::fixtures::unsolved,
+        solver::grade,
+    };
+
+    #[test]
+    fn test_trace_solve() {
+        let puzzle = unsolved();
+        let trace = trace_solve(&puzzle, &[]);
+
+        assert!(trace.solved);
+        assert_eq!(trace.solver_version, SOLVER_VERSION);
+        assert_eq!(
+            trace.digits_after(trace.steps.len()),
+            [vec![0, 0, 0, 0], vec![0, 9, 7, 0], vec![0, 8, 4, 0]]
+        );
+        assert_eq!(trace.candidates_after(0)[1][1], DigitSet::ALL);
+        assert_eq!(trace.candidates_after(1)[1][1], DigitSet::from_iter([8, 9]));
+
+        let json = trace.to_json();
+        assert!(json.contains("\"technique\": \"run_combinations\""));
+        assert!(json.contains("\"is_vertical\": true"));
+        assert_eq!(Trace::from_json(&json).unwrap(), trace);
+    }
+
+    #[test]
+    fn test_generated_board() {
+        let puzzle = generate_puzzle(&GenConfig::new(12, 12).with_seed(2)).unwrap();
+        let solution = puzzle.solution.as_ref().unwrap();
+        let trace = trace_solve(&puzzle, &[]);
+
+        assert_eq!(trace.solved, grade(&puzzle).is_some());
+        let digits = trace.digits_after(trace.steps.len());
+        for (x, y) in puzzle.fillable_cells() {
+            let digit = digits[y as usize][x as usize];
+            assert!(digit == 0 || digit == solution[y as usize][x as usize]);
+        }
+
+        assert_eq!(Trace::from_json(&trace.to_json()).unwrap(), trace);
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let puzzle = unsolved();
+        let trace = trace_solve(&puzzle, &[]);
+
+        let mut reworded = trace.clone();
+        reworded.steps[0].explanation = "Something else".to_string();
+        assert_eq!(trace.first_difference(&reworded), None);
+
+        let mut changed = trace.clone();
+        changed.steps[2].technique = Technique::Contradiction;
+        assert_eq!(trace.first_difference(&changed), Some(2));
+
+        let mut shorter = trace.clone();
+        shorter.steps.pop();
+        assert_eq!(trace.first_difference(&shorter), Some(shorter.steps.len()));
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        let puzzle = unsolved();
+        let trace = trace_solve(&puzzle, &[]);
+        let error = |edit: fn(&mut Trace)| {
+            let mut bad = trace.clone();
+            edit(&mut bad);
+            Trace::from_json(&bad.to_json()).unwrap_err().message
+        };
+
+        assert_eq!(
+            error(|t| t.rules[0].num_cells = 200),
+            "rule at 1,0 goes outside the 4x3 puzzle"
+        );
+        assert_eq!(
+            error(|t| t.rules[0].y = 250),
+            "rule at 1,250 goes outside the 4x3 puzzle"
+        );
+        assert_eq!(
+            error(|t| t.rules[0].value = 18),
+            "rule at 1,0 can't have 2 cells adding up to 18"
+        );
+        assert_eq!(
+            error(|t| t.start[2].push(0)),
+            "start digits don't match the puzzle size"
+        );
+        assert_eq!(
+            error(|t| t.start[1][1] = 10),
+            "start digits don't match the puzzle size"
+        );
+        assert_eq!(
+            error(|t| t.steps[0].eliminated[0].x = 9),
+            "step 1 goes outside the puzzle"
+        );
+        assert_eq!(
+            error(|t| t.steps[0].eliminated[0].digits.push(0)),
+            "step 1 goes outside the puzzle"
+        );
+        let placed = error(|t| {
+            let placed = t.steps.iter_mut().find_map(|s| s.placed.as_mut()).unwrap();
+            placed.digit = 10;
+        });
+        assert!(placed.ends_with("goes outside the puzzle"));
+        assert!(Trace::from_json("{").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Counting a puzzle's solutions, so generation can make sure there's only the one.
+//!
+//! The search narrows candidates the same way the solver does and only guesses when that gets stuck, on the cell
+//! with the fewest candidates left. Guesses try the puzzle's own solution first when it has one, so a second solution
+//! that differs from it in a few cells turns up after only a few guesses.
+
+use crate::{
+    candidates::{RuleIndex, propagate, propagate_cell},
+    combinations::DigitSet,
+    puzzle::{Puzzle, Rule},
+};
+
+// Searches this long only happen on very open puzzles, which are all but certain to have more solutions
+const MAX_GUESSES: usize = 100_000;
+
+/// Up to `limit` solutions of the puzzle's rules, each a digit for every cell, 0 for clue cells. `None` if the search
+/// gave up before finding `limit` of them or ruling out any more
+pub fn find_solutions(puzzle: &Puzzle, limit: usize) -> Option<Vec<Vec<Vec<u8>>>> {
+    let mut cells = vec![vec![DigitSet::EMPTY; puzzle.width as usize]; puzzle.height as usize];
+    for (x, y) in puzzle.fillable_cells() {
+        cells[y as usize][x as usize] = DigitSet::ALL;
+    }
+
+    let mut search = Search {
+        puzzle,
+        index: RuleIndex::new(puzzle),
+        fillable: puzzle.fillable_cells().collect(),
+        limit,
+        solutions: Vec::new(),
+        guesses: 0,
+    };
+
+    if limit > 0 && propagate(puzzle, &search.index, &mut cells) {
+        search.search(cells)?;
+    }
+
+    Some(search.solutions)
+}
+
+/// Number of solutions, counting no further than `limit`. `None` if the search gave up
+pub fn count_solutions(puzzle: &Puzzle, limit: usize) -> Option<usize> {
+    find_solutions(puzzle, limit).map(|s| s.len())
+}
+
+/// Whether the puzzle's rules have exactly one solution. A search that gives up counts as not unique
+pub fn is_unique(puzzle: &Puzzle) -> bool {
+    count_solutions(puzzle, 2) == Some(1)
+}
+
+/// Turns a fillable cell of a filled puzzle into a clue cell, splitting the runs through it and taking its digit out
+/// of their sums. `None` if that would leave a run outside of 2-9 cells or the fillable cells in more than one piece
+pub(crate) fn add_clue(puzzle: &Puzzle, x: u8, y: u8) -> Option<Puzzle> {
+    let mut solution = puzzle.solution.clone()?;
+    solution[y as usize][x as usize] = 0;
+
+    // Row and column 0 are always clues, so the shape starts at 1
+    let shape: Vec<Vec<bool>> = (1..puzzle.height)
+        .map(|sy| {
+            (1..puzzle.width)
+                .map(|sx| (sx, sy) != (x, y) && puzzle.is_fillable(sx, sy))
+                .collect()
+        })
+        .collect();
+    let clued = Puzzle::from_shape(&shape);
+
+    if clued.rules.iter().any(|r| !(2..=9).contains(&r.num_cells)) || !is_connected(&clued) {
+        return None;
+    }
+
+    let rules = clued
+        .rules
+        .iter()
+        .map(|r| Rule {
+            value: r
+                .cells()
+                .map(|(x, y)| solution[y as usize][x as usize])
+                .sum(),
+            ..*r
+        })
+        .collect();
+
+    Some(Puzzle::new(
+        clued.width,
+        clued.height,
+        rules,
+        Some(solution),
+    ))
+}
+
+fn is_connected(puzzle: &Puzzle) -> bool {
+    let Some(start) = puzzle.fillable_cells().next() else {
+        return true;
+    };
+
+    let mut seen = vec![vec![false; puzzle.width as usize]; puzzle.height as usize];
+    seen[start.1 as usize][start.0 as usize] = true;
+    let mut stack = vec![start];
+    let mut reached = 0;
+
+    while let Some((x, y)) = stack.pop() {
+        reached += 1;
+
+        // Fillable cells are never on row or column 0, so the neighbours can't underflow
+        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if puzzle.is_fillable(nx, ny) && !seen[ny as usize][nx as usize] {
+                seen[ny as usize][nx as usize] = true;
+                stack.push((nx, ny));
+            }
+        }
+    }
+
+    reached == puzzle.fillable_cells().count()
+}
+
+struct Search<'a> {
+    puzzle: &'a Puzzle,
+    index: RuleIndex,
+    fillable: Vec<(u8, u8)>,
+    limit: usize,
+    solutions: Vec<Vec<Vec<u8>>>,
+    guesses: usize,
+}
+
+impl Search<'_> {
+    /// Looks for solutions from fully narrowed candidates, `None` if it ran out of guesses
+    fn search(&mut self, cells: Vec<Vec<DigitSet>>) -> Option<()> {
+        let open = self
+            .fillable
+            .iter()
+            .copied()
+            .filter(|&(x, y)| cells[y as usize][x as usize].len() > 1)
+            .min_by_key(|&(x, y)| cells[y as usize][x as usize].len());
+
+        // Narrowing only leaves single candidates everywhere once every rule adds up
+        let Some((x, y)) = open else {
+            self.solutions.push(
+                cells
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|set| set.iter().next().unwrap_or(0))
+                            .collect()
+                    })
+                    .collect(),
+            );
+            return Some(());
+        };
+
+        let mut digits: Vec<u8> = cells[y as usize][x as usize].iter().collect();
+        if let Some(solution) = &self.puzzle.solution {
+            let known = solution[y as usize][x as usize];
+            digits.sort_by_key(|&d| d != known);
+        }
+
+        for digit in digits {
+            self.guesses += 1;
+            if self.guesses > MAX_GUESSES {
+                return None;
+            }
+
+            let mut trial = cells.clone();
+            trial[y as usize][x as usize] = DigitSet::single(digit);
+
+            if propagate_cell(self.puzzle, &self.index, &mut trial, x, y) {
+                self.search(trial)?;
+
+                if self.solutions.len() >= self.limit {
+                    break;
+                }
+            }
+        }
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        grid_gen::{GenConfig, generate_puzzle},
+        puzzle::fixtures::{ambiguous, unsolved},
+    };
+
+    #[test]
+    fn test_count_solutions() {
+        let puzzle = unsolved();
+        assert_eq!(count_solutions(&puzzle, 2), Some(1));
+        assert!(is_unique(&puzzle));
+
+        let ambiguous = ambiguous();
+        assert_eq!(
+            find_solutions(&ambiguous, 5),
+            Some(vec![
+                vec![vec![0, 0, 0], vec![0, 1, 2], vec![0, 2, 1]],
+                vec![vec![0, 0, 0], vec![0, 2, 1], vec![0, 1, 2]],
+            ])
+        );
+        assert_eq!(count_solutions(&ambiguous, 1), Some(1));
+        assert_eq!(count_solutions(&ambiguous, 0), Some(0));
+        assert!(!is_unique(&ambiguous));
+    }
+
+    #[test]
+    fn test_add_clue() {
+        // Any clue in a 2x2 block leaves runs of one cell
+        let puzzle = unsolved();
+        assert_eq!(add_clue(&puzzle, 1, 1), None);
+
+        let puzzle = generate_puzzle(&GenConfig::new(10, 10).with_seed(5)).unwrap();
+        let (x, y, clued) = puzzle
+            .fillable_cells()
+            .find_map(|(x, y)| add_clue(&puzzle, x, y).map(|p| (x, y, p)))
+            .unwrap();
+        let solution = clued.solution.as_ref().unwrap();
+
+        assert!(!clued.is_fillable(x, y));
+        assert_eq!(
+            clued.fillable_cells().count(),
+            puzzle.fillable_cells().count() - 1
+        );
+        for rule in &clued.rules {
+            let sum: u8 = rule
+                .cells()
+                .map(|(x, y)| solution[y as usize][x as usize])
+                .sum();
+            assert_eq!(sum, rule.value);
+        }
+    }
+
+    #[test]
+    fn test_generated_puzzles() {
+        for seed in 0..3 {
+            let puzzle = generate_puzzle(&GenConfig::new(12, 12).with_seed(seed)).unwrap();
+            let solutions = find_solutions(&puzzle, 2).unwrap();
+
+            assert_eq!(solutions, vec![puzzle.solution.clone().unwrap()]);
+        }
+    }
+}
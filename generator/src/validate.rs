@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combinations::{DigitSet, combinations},
+    puzzle::{Puzzle, Rule},
+};
+
+/// Mirrors `Rule.RuleState` in the game
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RuleState {
+    Incomplete,
+    Complete,
+    Invalid,
+}
+
+/// Why a rule is invalid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Violation {
+    /// A cell holds something other than 1-9, which the game never allows
+    InvalidDigit,
+    /// The same digit is in the run more than once
+    Duplicate { digit: u8 },
+    /// The placed digits already add up to more than the value
+    Overshoot,
+    /// Every cell is filled but they don't add up to the value
+    WrongSum,
+    /// No unused digits in the empty cells can make up the rest of the value
+    Unreachable,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleReport {
+    pub rule: Rule,
+    pub state: RuleState,
+    pub violation: Option<Violation>,
+    /// Cells responsible for the violation, empty unless the rule is invalid
+    pub offending: Vec<(u8, u8)>,
+}
+
+/// State of every rule on the board
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardReport {
+    pub rules: Vec<RuleReport>,
+}
+
+impl BoardReport {
+    pub fn is_valid(&self) -> bool {
+        self.rules.iter().all(|r| r.state != RuleState::Invalid)
+    }
+
+    /// Whether every rule is complete, meaning the puzzle is solved
+    pub fn is_solved(&self) -> bool {
+        self.rules.iter().all(|r| r.state == RuleState::Complete)
+    }
+
+    /// Every offending cell on the board, each once, in the order the rules list them
+    pub fn offending_cells(&self) -> Vec<(u8, u8)> {
+        let mut cells: Vec<(u8, u8)> = Vec::new();
+
+        for &cell in self.rules.iter().flat_map(|r| &r.offending) {
+            if !cells.contains(&cell) {
+                cells.push(cell);
+            }
+        }
+
+        cells
+    }
+}
+
+/// Checks one rule against `digits`, 0 being an empty cell.
+///
+/// Classifies the rule the same way `Rule.validate_rule` does, and also calls it invalid when the empty cells can't be
+/// completed with unused digits. A value of 0 means the sum isn't known, so only duplicates are checked
+pub fn validate_rule(rule: &Rule, digits: &[Vec<u8>]) -> RuleReport {
+    let digit_at = |(x, y): (u8, u8)| {
+        digits
+            .get(y as usize)
+            .and_then(|r| r.get(x as usize))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let filled: Vec<((u8, u8), u8)> = rule
+        .cells()
+        .map(|c| (c, digit_at(c)))
+        .filter(|&(_, d)| d != 0)
+        .collect();
+    let empty = rule.num_cells as usize - filled.len();
+
+    let invalid = |violation: Violation, offending: Vec<(u8, u8)>| RuleReport {
+        rule: *rule,
+        state: RuleState::Invalid,
+        violation: Some(violation),
+        offending,
+    };
+
+    let bad: Vec<(u8, u8)> = filled
+        .iter()
+        .filter(|&&(_, d)| d > 9)
+        .map(|&(c, _)| c)
+        .collect();
+
+    if !bad.is_empty() {
+        return invalid(Violation::InvalidDigit, bad);
+    }
+
+    let mut used = DigitSet::EMPTY;
+    for &(_, digit) in &filled {
+        if used.contains(digit) {
+            let offending = filled
+                .iter()
+                .filter(|&&(_, d)| d == digit)
+                .map(|&(c, _)| c)
+                .collect();
+
+            return invalid(Violation::Duplicate { digit }, offending);
+        }
+
+        used = used.with(digit);
+    }
+
+    let sum: u32 = filled.iter().map(|&(_, d)| d as u32).sum();
+    let value = rule.value as u32;
+    let filled_cells = || filled.iter().map(|&(c, _)| c).collect();
+
+    if rule.value != 0 {
+        if sum > value {
+            return invalid(Violation::Overshoot, filled_cells());
+        }
+
+        if empty == 0 && sum != value {
+            return invalid(Violation::WrongSum, filled_cells());
+        }
+
+        let reachable = combinations(empty as u8, (value - sum) as u8)
+            .iter()
+            .any(|c| c.intersection(used).is_empty());
+
+        if empty > 0 && !reachable {
+            return invalid(Violation::Unreachable, filled_cells());
+        }
+    }
+
+    RuleReport {
+        rule: *rule,
+        state: if empty == 0 {
+            RuleState::Complete
+        } else {
+            RuleState::Incomplete
+        },
+        violation: None,
+        offending: Vec::new(),
+    }
+}
+
+/// Checks every rule of the puzzle against `digits`, 0 being an empty cell
+pub fn validate_board(puzzle: &Puzzle, digits: &[Vec<u8>]) -> BoardReport {
+    BoardReport {
+        rules: puzzle
+            .rules
+            .iter()
+            .map(|r| validate_rule(r, digits))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::fixtures::unsolved;
+
+    fn state(value: u8, run: &[u8]) -> (RuleState, Option<Violation>, Vec<(u8, u8)>) {
+        let rule = Rule::new_horiz(0, 0, run.len() as u8, value);
+        let mut row = vec![0];
+        row.extend_from_slice(run);
+
+        let report = validate_rule(&rule, &[row]);
+        (report.state, report.violation, report.offending)
+    }
+
+    #[test]
+    fn test_validate_rule() {
+        use RuleState::*;
+
+        assert_eq!(state(10, &[0, 0, 0]), (Incomplete, None, vec![]));
+        assert_eq!(state(10, &[1, 0, 0]), (Incomplete, None, vec![]));
+        assert_eq!(state(10, &[1, 2, 7]), (Complete, None, vec![]));
+        assert_eq!(
+            state(10, &[3, 0, 3]),
+            (
+                Invalid,
+                Some(Violation::Duplicate { digit: 3 }),
+                vec![(1, 0), (3, 0)]
+            )
+        );
+        assert_eq!(
+            state(10, &[9, 0, 2]),
+            (Invalid, Some(Violation::Overshoot), vec![(1, 0), (3, 0)])
+        );
+        assert_eq!(
+            state(10, &[1, 2, 3]),
+            (
+                Invalid,
+                Some(Violation::WrongSum),
+                vec![(1, 0), (2, 0), (3, 0)]
+            )
+        );
+
+        // Perfect sum with a cell left over, as in the game
+        assert_eq!(state(10, &[1, 9, 0]).1, Some(Violation::Unreachable));
+        // 4 left for two cells can only be 1+3, but 1 is used. The game would call this incomplete
+        assert_eq!(
+            state(10, &[1, 5, 0, 0]),
+            (Invalid, Some(Violation::Unreachable), vec![(1, 0), (2, 0)])
+        );
+        // 9 left for one cell when 9 is used
+        assert_eq!(state(18, &[9, 0]).1, Some(Violation::Unreachable));
+
+        // Unknown values only check duplicates
+        assert_eq!(state(0, &[4, 5]), (Complete, None, vec![]));
+
+        // Out of range digits are flagged before anything else, and can't overflow the sum
+        assert_eq!(
+            state(17, &[10, 7]),
+            (Invalid, Some(Violation::InvalidDigit), vec![(1, 0)])
+        );
+        assert_eq!(
+            state(0, &[10, 0, 10]),
+            (Invalid, Some(Violation::InvalidDigit), vec![(1, 0), (3, 0)])
+        );
+        assert_eq!(
+            state(45, &[200, 200, 9]),
+            (Invalid, Some(Violation::InvalidDigit), vec![(1, 0), (2, 0)])
+        );
+    }
+
+    #[test]
+    fn test_validate_board() {
+        let puzzle = unsolved();
+
+        let empty = vec![vec![0; 4]; 3];
+        let report = validate_board(&puzzle, &empty);
+        assert!(report.is_valid());
+        assert!(!report.is_solved());
+
+        let solved = vec![vec![0; 4], vec![0, 9, 7, 0], vec![0, 8, 4, 0]];
+        assert!(validate_board(&puzzle, &solved).is_solved());
+
+        // The 16 across would need another 8. The 17 down is still fine with a 9 below
+        let wrong = vec![vec![0; 4], vec![0, 8, 0, 0], vec![0; 4]];
+        let report = validate_board(&puzzle, &wrong);
+        assert!(!report.is_valid());
+        assert_eq!(
+            report
+                .rules
+                .iter()
+                .filter(|r| r.state == RuleState::Invalid)
+                .count(),
+            1
+        );
+        assert_eq!(report.offending_cells(), vec![(1, 1)]);
+    }
+}